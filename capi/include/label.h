@@ -0,0 +1,107 @@
+#ifndef LABEL_H
+#define LABEL_H
+
+/* Generated by cbindgen from label's src/ffi.rs; do not edit. */
+
+#include <stdbool.h>
+
+/* An opaque label handle. */
+typedef struct DCLabel DCLabel;
+
+/**
+ * The result of a call through the C interface.
+ */
+typedef enum LabelStatus {
+  LABEL_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null.
+   */
+  LABEL_STATUS_NULL_POINTER = 1,
+  /**
+   * A string argument was not valid UTF-8.
+   */
+  LABEL_STATUS_INVALID_UTF8 = 2,
+  /**
+   * A string argument was not a valid label.
+   */
+  LABEL_STATUS_PARSE_ERROR = 3,
+  /**
+   * The library panicked. This is a bug.
+   */
+  LABEL_STATUS_PANIC = 4,
+} LabelStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parse the NUL-terminated string `text` into a new label, stored in `*out`.
+ *
+ * # Safety
+ *
+ * `text` must be null or a valid NUL-terminated string, and `out` must be null or valid for
+ * writes.
+ */
+enum LabelStatus label_dclabel_parse(const char *text, DCLabel **out);
+
+/**
+ * Store the join of `lhs` and `rhs` in `*out`, as a new label.
+ *
+ * # Safety
+ *
+ * `lhs` and `rhs` must be null or live handles, and `out` must be null or valid for writes.
+ */
+enum LabelStatus label_dclabel_join(const DCLabel *lhs, const DCLabel *rhs, DCLabel **out);
+
+/**
+ * Store the meet of `lhs` and `rhs` in `*out`, as a new label.
+ *
+ * # Safety
+ *
+ * `lhs` and `rhs` must be null or live handles, and `out` must be null or valid for writes.
+ */
+enum LabelStatus label_dclabel_meet(const DCLabel *lhs, const DCLabel *rhs, DCLabel **out);
+
+/**
+ * Store whether `lhs` can flow to `rhs` in `*out`.
+ *
+ * # Safety
+ *
+ * `lhs` and `rhs` must be null or live handles, and `out` must be null or valid for writes.
+ */
+enum LabelStatus label_dclabel_can_flow_to(const DCLabel *lhs, const DCLabel *rhs, bool *out);
+
+/**
+ * Store the text form of `label` in `*out`, as a new string to be released with
+ * `label_string_free`.
+ *
+ * # Safety
+ *
+ * `label` must be null or a live handle, and `out` must be null or valid for writes.
+ */
+enum LabelStatus label_dclabel_to_string(const DCLabel *label, char **out);
+
+/**
+ * Release a label. Null is ignored.
+ *
+ * # Safety
+ *
+ * `label` must be null or a handle returned by this library that has not been freed.
+ */
+void label_dclabel_free(DCLabel *label);
+
+/**
+ * Release a string returned by `label_dclabel_to_string`. Null is ignored.
+ *
+ * # Safety
+ *
+ * `text` must be null or a string returned by this library that has not been freed.
+ */
+void label_string_free(char *text);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LABEL_H */
@@ -0,0 +1,4 @@
+//! Builds `label`'s C interface, [`label::ffi`], as static and shared libraries for C and C++
+//! callers. The declarations are in `include/label.h`.
+
+pub use label::ffi::*;
@@ -0,0 +1,60 @@
+//! Checks that `include/label.h` matches the C interface, and runs the C harness against it.
+//!
+//! Set `LABEL_BLESS=1` to regenerate the header instead of comparing it.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn manifest_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let config = cbindgen::Config::from_file(manifest_dir().join("cbindgen.toml")).unwrap();
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(manifest_dir().join("../src/ffi.rs"))
+        .generate()
+        .unwrap();
+    let mut generated = Vec::new();
+    bindings.write(&mut generated);
+
+    let path = manifest_dir().join("include/label.h");
+    if env::var_os("LABEL_BLESS").is_some() {
+        fs::write(&path, &generated).unwrap();
+    }
+    let checked_in = fs::read(&path).unwrap();
+    assert!(
+        checked_in == generated,
+        "include/label.h is stale; rerun this test with LABEL_BLESS=1 to regenerate it"
+    );
+}
+
+/// The static library built alongside this test, next to it in the `deps` directory.
+fn static_lib() -> PathBuf {
+    env::current_exe().unwrap().with_file_name("liblabel_capi.a")
+}
+
+#[test]
+fn c_harness() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let exe = dir.join("label-ffi-harness");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(cc)
+        .args(["-std=c99", "-Wall", "-Werror", "-o"])
+        .arg(&exe)
+        .arg("-I")
+        .arg(manifest_dir().join("include"))
+        .arg(manifest_dir().join("tests/harness.c"))
+        .arg(static_lib())
+        .args(["-lpthread", "-ldl", "-lm"])
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "compiling the C harness failed");
+
+    let output = Command::new(&exe).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,66 @@
+/* Exercises the C interface through the generated header. */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "label.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                        \
+    do {                                                                   \
+        if (!(cond)) {                                                     \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,         \
+                    __LINE__, #cond);                                      \
+            failures++;                                                    \
+        }                                                                  \
+    } while (0)
+
+int main(void) {
+    DCLabel *alice = NULL, *bob = NULL, *joined = NULL, *met = NULL;
+    char *text = NULL;
+    bool flows = false;
+
+    CHECK(label_dclabel_parse("<alice, True>", &alice) == LABEL_STATUS_OK);
+    CHECK(label_dclabel_parse("<bob, True>", &bob) == LABEL_STATUS_OK);
+
+    CHECK(label_dclabel_join(alice, bob, &joined) == LABEL_STATUS_OK);
+    CHECK(label_dclabel_to_string(joined, &text) == LABEL_STATUS_OK);
+    CHECK(text != NULL && strcmp(text, "<(alice) /\\ (bob), True>") == 0);
+    label_string_free(text);
+
+    CHECK(label_dclabel_meet(alice, bob, &met) == LABEL_STATUS_OK);
+    CHECK(label_dclabel_to_string(met, &text) == LABEL_STATUS_OK);
+    CHECK(text != NULL && strcmp(text, "<(alice \\/ bob), True>") == 0);
+    label_string_free(text);
+
+    CHECK(label_dclabel_can_flow_to(alice, joined, &flows) == LABEL_STATUS_OK);
+    CHECK(flows);
+    CHECK(label_dclabel_can_flow_to(joined, alice, &flows) == LABEL_STATUS_OK);
+    CHECK(!flows);
+    CHECK(label_dclabel_can_flow_to(met, bob, &flows) == LABEL_STATUS_OK);
+    CHECK(flows);
+
+    /* Errors leave the out-pointer untouched. */
+    DCLabel *bad = NULL;
+    CHECK(label_dclabel_parse("<alice", &bad) == LABEL_STATUS_PARSE_ERROR);
+    CHECK(label_dclabel_parse("<\xff, True>", &bad) == LABEL_STATUS_INVALID_UTF8);
+    CHECK(label_dclabel_parse(NULL, &bad) == LABEL_STATUS_NULL_POINTER);
+    CHECK(label_dclabel_join(alice, NULL, &bad) == LABEL_STATUS_NULL_POINTER);
+    CHECK(label_dclabel_can_flow_to(alice, bob, NULL) == LABEL_STATUS_NULL_POINTER);
+    CHECK(bad == NULL);
+
+    label_dclabel_free(alice);
+    label_dclabel_free(bob);
+    label_dclabel_free(joined);
+    label_dclabel_free(met);
+    label_dclabel_free(NULL);
+    label_string_free(NULL);
+
+    if (failures != 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return EXIT_FAILURE;
+    }
+    return EXIT_SUCCESS;
+}
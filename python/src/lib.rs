@@ -0,0 +1,3 @@
+//! Builds [`label::python`] as the `label` Python extension module.
+
+pub use label::python::*;
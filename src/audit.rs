@@ -0,0 +1,290 @@
+//! Auditing of privileged operations.
+//!
+//! A [`Privilege`] can only be exercised through an [`AuditedPrivilege`], which pairs it with an
+//! [`AuditSink`]. Every privileged operation on a [`Context`](crate::context::Context) records an
+//! [`AuditEvent`] before it takes effect, and is refused if the event cannot be recorded.
+//!
+//! Audited privileges are only handed out by the [`PrivilegeRoot`], of which there is one per
+//! process, so every privileged operation is recorded by the sink the application configured.
+//! Tests can obtain as many roots as they need with `PrivilegeRoot::for_testing`, under the
+//! `testing` feature.
+
+use std::fmt::{self, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::dclabel::{DCLabel, Privilege};
+
+/// The kind of privileged operation that was performed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditKind {
+    /// The current label was lowered.
+    Declassify,
+    /// A labeled value was given a label it could only flow to using the privilege.
+    Relabel,
+    /// A write was permitted only because of the privilege.
+    Write,
+}
+
+impl fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditKind::Declassify => write!(f, "declassify"),
+            AuditKind::Relabel => write!(f, "relabel"),
+            AuditKind::Write => write!(f, "write"),
+        }
+    }
+}
+
+/// A record of one use of a privilege.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEvent {
+    pub kind: AuditKind,
+    /// The label before the operation: the current label, joined with the PC label for a write,
+    /// or the label of the relabeled value.
+    pub old_label: DCLabel,
+    /// The label after the operation, or the label written to.
+    pub new_label: DCLabel,
+    pub privilege: Privilege,
+    pub justification: String,
+    pub timestamp: SystemTime,
+}
+
+impl AuditEvent {
+    /// Render the event as a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        let millis = self.timestamp.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        format!(
+            "{{\"kind\":{},\"old_label\":{},\"new_label\":{},\"privilege\":{},\"justification\":{},\"timestamp_ms\":{}}}",
+            json_string(&self.kind.to_string()),
+            json_string(&self.old_label.to_string()),
+            json_string(&self.new_label.to_string()),
+            json_string(&self.privilege.to_string()),
+            json_string(&self.justification),
+            millis,
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A destination for audit events.
+pub trait AuditSink: Send + Sync {
+    /// Record `event`. An error causes the privileged operation to be refused.
+    fn record(&self, event: &AuditEvent) -> io::Result<()>;
+}
+
+/// Keeps audit events in memory.
+#[derive(Default)]
+pub struct MemorySink {
+    events: Mutex<Vec<AuditEvent>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        MemorySink::default()
+    }
+
+    /// The events recorded so far, oldest first.
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl AuditSink for MemorySink {
+    fn record(&self, event: &AuditEvent) -> io::Result<()> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).push(event.clone());
+        Ok(())
+    }
+}
+
+/// Appends audit events to a file, one JSON object per line.
+pub struct JsonLinesSink {
+    file: Mutex<File>,
+}
+
+impl JsonLinesSink {
+    /// Open `path` for appending, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesSink { file: Mutex::new(file) })
+    }
+}
+
+impl AuditSink for JsonLinesSink {
+    fn record(&self, event: &AuditEvent) -> io::Result<()> {
+        let mut line = event.to_json();
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(line.as_bytes())?;
+        file.flush()
+    }
+}
+
+/// Passes audit events to a function.
+pub struct CallbackSink<F>(F);
+
+impl<F: Fn(&AuditEvent) -> io::Result<()> + Send + Sync> CallbackSink<F> {
+    pub fn new(f: F) -> Self {
+        CallbackSink(f)
+    }
+}
+
+impl<F: Fn(&AuditEvent) -> io::Result<()> + Send + Sync> AuditSink for CallbackSink<F> {
+    fn record(&self, event: &AuditEvent) -> io::Result<()> {
+        (self.0)(event)
+    }
+}
+
+/// A privilege that records every use of itself.
+///
+/// `P` describes the privilege: a DC [`Privilege`] for the privileged operations on a
+/// [`Context`](crate::context::Context), or any other
+/// [`PrivilegeDescription`](crate::gate::PrivilegeDescription) to call a
+/// [`Gate`](crate::gate::Gate) with.
+#[derive(Clone)]
+pub struct AuditedPrivilege<P = Privilege> {
+    privilege: P,
+    sink: Arc<dyn AuditSink>,
+}
+
+impl<P> AuditedPrivilege<P> {
+    pub(crate) fn new(privilege: P, sink: Arc<dyn AuditSink>) -> Self {
+        AuditedPrivilege { privilege, sink }
+    }
+
+    pub fn privilege(&self) -> &P {
+        &self.privilege
+    }
+}
+
+impl AuditedPrivilege {
+
+    pub(crate) fn record(
+        &self, kind: AuditKind, old_label: &DCLabel, new_label: &DCLabel, justification: &str,
+    ) -> crate::Result<()> {
+        let event = AuditEvent {
+            kind,
+            old_label: old_label.clone(),
+            new_label: new_label.clone(),
+            privilege: self.privilege.clone(),
+            justification: justification.to_string(),
+            timestamp: SystemTime::now(),
+        };
+        self.sink.record(&event).map_err(|e| crate::Error::AuditFailed(e.to_string()))
+    }
+}
+
+impl<P: fmt::Display> fmt::Debug for AuditedPrivilege<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AuditedPrivilege({})", self.privilege)
+    }
+}
+
+static ROOT_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// The authority to exercise privileges.
+///
+/// The first call to [`PrivilegeRoot::install`] obtains the root, normally in `main` before any
+/// untrusted code runs, and every later call fails. Code that cannot reach the root therefore
+/// cannot obtain an [`AuditedPrivilege`], whatever [`Privilege`] it holds.
+pub struct PrivilegeRoot {
+    sink: Arc<dyn AuditSink>,
+}
+
+impl PrivilegeRoot {
+    /// Obtain the root, recording the use of every privilege it hands out to `sink`. Returns
+    /// `None` if the root has already been obtained.
+    pub fn install(sink: Arc<dyn AuditSink>) -> Option<Self> {
+        if ROOT_INSTALLED.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        Some(PrivilegeRoot { sink })
+    }
+
+    /// A root of its own, recording to `sink`, independently of [`PrivilegeRoot::install`].
+    ///
+    /// Only available in tests and under the `testing` feature, since it lets any code exercise
+    /// privileges without the application's sink seeing them.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn for_testing(sink: Arc<dyn AuditSink>) -> Self {
+        PrivilegeRoot { sink }
+    }
+
+    /// Allow `privilege` to be exercised, recording each use to the root's sink.
+    pub fn audit<P>(&self, privilege: P) -> AuditedPrivilege<P> {
+        AuditedPrivilege::new(privilege, self.sink.clone())
+    }
+}
+
+impl fmt::Debug for PrivilegeRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PrivilegeRoot")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_escapes() {
+        let event = AuditEvent {
+            kind: AuditKind::Declassify,
+            old_label: "<alice, True>".parse().unwrap(),
+            new_label: DCLabel::public(),
+            privilege: Privilege::new("alice"),
+            justification: "ticket \"42\"\n\u{1}".to_string(),
+            timestamp: UNIX_EPOCH + std::time::Duration::from_millis(1500),
+        };
+        assert_eq!(
+            event.to_json(),
+            "{\"kind\":\"declassify\",\"old_label\":\"<(alice), True>\",\"new_label\":\"<True, True>\",\
+             \"privilege\":\"(alice)\",\"justification\":\"ticket \\\"42\\\"\\n\\u0001\",\"timestamp_ms\":1500}"
+        );
+    }
+
+    /// The only test to install the root, since it can only be installed once per process.
+    #[test]
+    fn root_is_installed_once() {
+        assert!(PrivilegeRoot::install(Arc::new(MemorySink::new())).is_some());
+        assert!(PrivilegeRoot::install(Arc::new(MemorySink::new())).is_none());
+    }
+
+    #[test]
+    fn root_records_to_its_sink() {
+        let policy: crate::policy::Policy = "principal alice".parse().unwrap();
+        let sink = Arc::new(MemorySink::new());
+        let root = PrivilegeRoot::for_testing(sink.clone());
+        let other = Arc::new(MemorySink::new());
+        let _other_root = PrivilegeRoot::for_testing(other.clone());
+
+        let alice = root.audit(policy.privilege("alice").unwrap());
+        let mut ctx = crate::context::Context::new("<alice, True>".parse().unwrap(), DCLabel::top()).unwrap();
+        ctx.declassify(DCLabel::public(), &alice, "release").unwrap();
+        assert_eq!(sink.events().len(), 1);
+        assert!(other.events().is_empty());
+    }
+}
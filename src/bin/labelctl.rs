@@ -0,0 +1,194 @@
+//! Command-line tool for inspecting DC labels and policies.
+//!
+//! Labels are written in the text syntax accepted by `DCLabel`'s `FromStr` implementation, e.g.
+//! `'<alice /\ (hr \/ legal), True>'`.
+
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+
+use label::Label;
+use label::dclabel::{required_privilege, Conjunction, DCLabel};
+use label::policy::Policy;
+
+const USAGE: &str = "\
+usage: labelctl <command> [args]
+
+commands:
+    normalize <label>        print the label in normal form
+    flows <from> <to>        check whether <from> can flow to <to>, and why not
+    join <l1> <l2>           print the least upper bound of two labels
+    meet <l1> <l2>           print the greatest lower bound of two labels
+    explain <label>          describe who may read and who vouches for a label
+    check-policy <file>      validate a policy document";
+
+/// Exit status for a command that ran but answered "no" or found an error.
+const FAILURE: i32 = 1;
+/// Exit status for invalid invocations.
+const USAGE_ERROR: i32 = 2;
+
+fn parse(s: &str) -> Result<DCLabel, String> {
+    s.parse().map_err(|e| format!("invalid label {:?}: {}", s, e))
+}
+
+fn describe(conj: &Conjunction) -> Vec<String> {
+    let mut clauses: Vec<_> = conj.iter().collect();
+    clauses.sort();
+    clauses.iter().map(|d| {
+        let principals: Vec<&str> = d.iter().map(|p| p.as_str()).collect();
+        match principals.len() {
+            1 => principals[0].to_string(),
+            _ => format!("one of {}", principals.join(", ")),
+        }
+    }).collect()
+}
+
+fn explain(label: &DCLabel, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "{}", label)?;
+    let secrecy = label.secrecy();
+    if *secrecy == Conjunction::mk_true() {
+        writeln!(out, "secrecy: public, anyone may read it")?;
+    } else if *secrecy == Conjunction::mk_false() {
+        writeln!(out, "secrecy: top, no principal may read it")?;
+    } else {
+        writeln!(out, "secrecy: readers must speak for {}", describe(secrecy).join(" and "))?;
+    }
+    let integrity = label.integrity();
+    if *integrity == Conjunction::mk_true() {
+        writeln!(out, "integrity: no principal vouches for it")
+    } else if *integrity == Conjunction::mk_false() {
+        writeln!(out, "integrity: bottom, every principal vouches for it")
+    } else {
+        writeln!(out, "integrity: vouched for by {}", describe(integrity).join(" and "))
+    }
+}
+
+fn run(args: &[String], out: &mut dyn Write) -> Result<i32, String> {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let io = |e: io::Error| e.to_string();
+    match args.as_slice() {
+        ["normalize", l] => {
+            writeln!(out, "{}", parse(l)?).map_err(io)?;
+            Ok(0)
+        }
+        ["flows", from, to] => {
+            let (from, to) = (parse(from)?, parse(to)?);
+            if from.can_flow_to(&to) {
+                writeln!(out, "yes: {} can flow to {}", from, to).map_err(io)?;
+                Ok(0)
+            } else {
+                writeln!(out, "no: {} cannot flow to {}", from, to).map_err(io)?;
+                writeln!(out, "{}", from.explain_flow(&to)).map_err(io)?;
+                writeln!(out, "required privilege: {}", required_privilege(&from, &to)).map_err(io)?;
+                Ok(FAILURE)
+            }
+        }
+        ["join", l1, l2] => {
+            writeln!(out, "{}", parse(l1)?.join(&parse(l2)?)).map_err(io)?;
+            Ok(0)
+        }
+        ["meet", l1, l2] => {
+            writeln!(out, "{}", parse(l1)?.meet(&parse(l2)?)).map_err(io)?;
+            Ok(0)
+        }
+        ["explain", l] => {
+            explain(&parse(l)?, out).map_err(io)?;
+            Ok(0)
+        }
+        ["check-policy", path] => {
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            match text.parse::<Policy>() {
+                Ok(policy) => {
+                    let (principals, labels) = (policy.principals().count(), policy.labels().count());
+                    writeln!(out, "ok: {} principals, {} labels", principals, labels).map_err(io)?;
+                    for (name, label) in policy.labels() {
+                        writeln!(out, "  {} = {}", name, label).map_err(io)?;
+                    }
+                    Ok(0)
+                }
+                Err(e) => {
+                    writeln!(out, "{}: {}", path, e).map_err(io)?;
+                    Ok(FAILURE)
+                }
+            }
+        }
+        _ => {
+            writeln!(out, "{}", USAGE).map_err(io)?;
+            Ok(USAGE_ERROR)
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let stdout = io::stdout();
+    let code = match run(&args, &mut stdout.lock()) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("labelctl: {}", e);
+            FAILURE
+        }
+    };
+    process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labelctl(args: &[&str]) -> (Result<i32, String>, String) {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let mut out = Vec::new();
+        let result = run(&args, &mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn normalize() {
+        let (code, out) = labelctl(&["normalize", "<bob \\/ alice /\\ alice, True>"]);
+        assert_eq!(code, Ok(0));
+        assert_eq!(out, "<(alice), True>\n");
+    }
+
+    #[test]
+    fn flows() {
+        assert_eq!(labelctl(&["flows", "<True, True>", "<alice, True>"]).0, Ok(0));
+        let (code, out) = labelctl(&["flows", "<alice, True>", "<True, True>"]);
+        assert_eq!(code, Ok(FAILURE));
+        assert!(out.starts_with("no:"));
+        assert!(out.contains("secrecy clause (alice) of the source"), "{}", out);
+        assert!(out.ends_with("required privilege: (alice)\n"), "{}", out);
+    }
+
+    #[test]
+    fn join_and_meet() {
+        assert_eq!(labelctl(&["join", "<alice, True>", "<bob, True>"]).1, "<(alice) /\\ (bob), True>\n");
+        assert_eq!(labelctl(&["meet", "<alice, True>", "<bob, True>"]).1, "<(alice \\/ bob), True>\n");
+    }
+
+    #[test]
+    fn explain_label() {
+        let (_, out) = labelctl(&["explain", "<alice /\\ (hr \\/ legal), bob>"]);
+        assert!(out.contains("readers must speak for alice and one of hr, legal"), "{}", out);
+        assert!(out.contains("vouched for by bob"), "{}", out);
+    }
+
+    #[test]
+    fn check_policy() {
+        let path = std::env::temp_dir().join(format!("labelctl-{}.policy", process::id()));
+        fs::write(&path, "principal alice\nlabel pii = <alice, True>\nclearance bob = pii\n").unwrap();
+        let (code, out) = labelctl(&["check-policy", path.to_str().unwrap()]);
+        assert_eq!(code, Ok(FAILURE));
+        assert!(out.contains("line 3: undeclared principal `bob`"), "{}", out);
+
+        fs::write(&path, "principal alice\nlabel pii = <alice, True>\n").unwrap();
+        assert_eq!(labelctl(&["check-policy", path.to_str().unwrap()]).0, Ok(0));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        assert!(labelctl(&["normalize", "<alice"]).0.is_err());
+        assert_eq!(labelctl(&["frobnicate"]).0, Ok(USAGE_ERROR));
+    }
+}
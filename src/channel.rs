@@ -0,0 +1,119 @@
+use std::sync::mpsc;
+
+use crate::Label;
+use crate::context::Context;
+use crate::error::{Error, Result};
+
+/// Create a channel whose contents are protected by `label`.
+///
+/// Sending requires that the sender's current label can flow to `label`, and receiving taints
+/// the receiver's current label with `label`.
+pub fn labeled_channel<L: Label + Clone, T>(label: L) -> (LabeledSender<L, T>, LabeledReceiver<L, T>) {
+    let (tx, rx) = mpsc::channel();
+    (LabeledSender { label: label.clone(), inner: tx }, LabeledReceiver { label, inner: rx })
+}
+
+/// The sending half of a [`labeled_channel`].
+pub struct LabeledSender<L, T> {
+    label: L,
+    inner: mpsc::Sender<T>,
+}
+
+impl<L: Label + Clone, T> LabeledSender<L, T> {
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    /// Send `value` on the channel.
+    ///
+    /// Fails with [`Error::FlowViolation`] if the current label of `ctx` cannot flow to the
+    /// channel's label, or [`Error::ClearanceViolation`] if the channel's label is above the
+    /// clearance of `ctx`.
+    pub fn send(&self, ctx: &Context<L>, value: T) -> Result<()> {
+        ctx.guard_write(&self.label)?;
+        self.inner.send(value).map_err(|_| Error::Disconnected)
+    }
+}
+
+impl<L: Clone, T> Clone for LabeledSender<L, T> {
+    fn clone(&self) -> Self {
+        LabeledSender { label: self.label.clone(), inner: self.inner.clone() }
+    }
+}
+
+/// The receiving half of a [`labeled_channel`].
+pub struct LabeledReceiver<L, T> {
+    label: L,
+    inner: mpsc::Receiver<T>,
+}
+
+impl<L: Label + Clone, T> LabeledReceiver<L, T> {
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    /// Block until a value is available, tainting `ctx` with the channel's label.
+    ///
+    /// The context is tainted before blocking, since even learning that a message arrived is an
+    /// observation of the channel.
+    pub fn recv(&self, ctx: &mut Context<L>) -> Result<T> {
+        ctx.taint(&self.label)?;
+        self.inner.recv().map_err(|_| Error::Disconnected)
+    }
+
+    /// Receive a value if one is immediately available, tainting `ctx` with the channel's label.
+    pub fn try_recv(&self, ctx: &mut Context<L>) -> Result<Option<T>> {
+        ctx.taint(&self.label)?;
+        match self.inner.try_recv() {
+            Ok(value) => Ok(Some(value)),
+            Err(mpsc::TryRecvError::Empty) => Ok(None),
+            Err(mpsc::TryRecvError::Disconnected) => Err(Error::Disconnected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twolevel::TwoLevel;
+
+    #[test]
+    fn low_to_high() {
+        let (tx, rx) = labeled_channel(TwoLevel::High);
+        let sender = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let mut receiver = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+
+        tx.send(&sender, 1).unwrap();
+        assert_eq!(rx.recv(&mut receiver), Ok(1));
+        assert_eq!(receiver.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn high_cannot_send_low() {
+        let (tx, rx) = labeled_channel::<_, u32>(TwoLevel::Low);
+        let sender = Context::new(TwoLevel::High, TwoLevel::High).unwrap();
+        let mut receiver = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+
+        assert_eq!(tx.send(&sender, 1), Err(Error::FlowViolation));
+        assert_eq!(rx.try_recv(&mut receiver), Ok(None));
+    }
+
+    #[test]
+    fn receive_above_clearance() {
+        let (tx, rx) = labeled_channel(TwoLevel::High);
+        let sender = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let mut receiver = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+
+        tx.send(&sender, 1).unwrap();
+        assert_eq!(rx.recv(&mut receiver), Err(Error::ClearanceViolation));
+        assert_eq!(receiver.label(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn disconnected() {
+        let (tx, rx) = labeled_channel::<_, u32>(TwoLevel::Low);
+        let mut receiver = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        drop(tx);
+        assert_eq!(rx.recv(&mut receiver), Err(Error::Disconnected));
+    }
+}
@@ -0,0 +1,242 @@
+//! Collections whose elements each carry their own label.
+//!
+//! A result set of mixed sensitivity can be kept in one collection and filtered by what a
+//! reader is cleared for with `visible_to`, rather than labeling the whole collection with the
+//! join of everything in it. The elements stay [`Labeled`], so reading one still goes through a
+//! [`Context`](crate::context::Context).
+
+use alloc::collections::{btree_map, BTreeMap};
+use alloc::vec::{self, Vec};
+use core::slice;
+
+use crate::Label;
+use crate::labeled::Labeled;
+
+/// The join of `labels`, or `None` if there are none.
+fn upper_bound<'a, L: Label + Clone + 'a>(mut labels: impl Iterator<Item = &'a L>) -> Option<L> {
+    let first = labels.next()?.clone();
+    Some(labels.fold(first, |bound, label| bound.join(label)))
+}
+
+/// A vector of individually labeled elements.
+#[derive(Clone, Debug)]
+pub struct LabeledVec<L, T> {
+    elements: Vec<Labeled<L, T>>,
+}
+
+impl<L, T> LabeledVec<L, T> {
+    pub fn new() -> Self {
+        LabeledVec { elements: Vec::new() }
+    }
+
+    pub fn push(&mut self, element: Labeled<L, T>) {
+        self.elements.push(element);
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Labeled<L, T>> {
+        self.elements.get(index)
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Labeled<L, T>> {
+        self.elements.iter()
+    }
+}
+
+impl<L: Label + Clone, T> LabeledVec<L, T> {
+    /// The elements whose label can flow to `clearance`, in order.
+    pub fn visible_to<'a>(&'a self, clearance: &'a L) -> impl Iterator<Item = &'a Labeled<L, T>> {
+        self.elements.iter().filter(move |e| e.label().can_flow_to(clearance))
+    }
+
+    /// The join of the labels of all elements, or `None` if there are none.
+    pub fn label_upper_bound(&self) -> Option<L> {
+        upper_bound(self.elements.iter().map(Labeled::label))
+    }
+}
+
+impl<L, T> Default for LabeledVec<L, T> {
+    fn default() -> Self {
+        LabeledVec::new()
+    }
+}
+
+impl<L, T> core::iter::FromIterator<Labeled<L, T>> for LabeledVec<L, T> {
+    fn from_iter<I: IntoIterator<Item = Labeled<L, T>>>(iter: I) -> Self {
+        LabeledVec { elements: iter.into_iter().collect() }
+    }
+}
+
+impl<L, T> Extend<Labeled<L, T>> for LabeledVec<L, T> {
+    fn extend<I: IntoIterator<Item = Labeled<L, T>>>(&mut self, iter: I) {
+        self.elements.extend(iter);
+    }
+}
+
+impl<L, T> IntoIterator for LabeledVec<L, T> {
+    type Item = Labeled<L, T>;
+    type IntoIter = vec::IntoIter<Labeled<L, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.into_iter()
+    }
+}
+
+impl<'a, L, T> IntoIterator for &'a LabeledVec<L, T> {
+    type Item = &'a Labeled<L, T>;
+    type IntoIter = slice::Iter<'a, Labeled<L, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+
+/// A map from keys to individually labeled values.
+///
+/// Only the values are labeled: the keys, and so which keys are present, are visible to anyone
+/// holding the map.
+#[derive(Clone, Debug)]
+pub struct LabeledMap<K, L, V> {
+    entries: BTreeMap<K, Labeled<L, V>>,
+}
+
+impl<K: Ord, L, V> LabeledMap<K, L, V> {
+    pub fn new() -> Self {
+        LabeledMap { entries: BTreeMap::new() }
+    }
+
+    /// Insert `value` under `key`, returning the value previously there.
+    pub fn insert(&mut self, key: K, value: Labeled<L, V>) -> Option<Labeled<L, V>> {
+        self.entries.insert(key, value)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&Labeled<L, V>> {
+        self.entries.get(key)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<Labeled<L, V>> {
+        self.entries.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries, in key order.
+    pub fn iter(&self) -> btree_map::Iter<'_, K, Labeled<L, V>> {
+        self.entries.iter()
+    }
+}
+
+impl<K: Ord, L: Label + Clone, V> LabeledMap<K, L, V> {
+    /// The entries whose value's label can flow to `clearance`, in key order.
+    pub fn visible_to<'a>(
+        &'a self, clearance: &'a L,
+    ) -> impl Iterator<Item = (&'a K, &'a Labeled<L, V>)> {
+        self.entries.iter().filter(move |(_, v)| v.label().can_flow_to(clearance))
+    }
+
+    /// The join of the labels of all values, or `None` if there are none.
+    pub fn label_upper_bound(&self) -> Option<L> {
+        upper_bound(self.entries.values().map(Labeled::label))
+    }
+}
+
+impl<K: Ord, L, V> Default for LabeledMap<K, L, V> {
+    fn default() -> Self {
+        LabeledMap::new()
+    }
+}
+
+impl<K: Ord, L, V> core::iter::FromIterator<(K, Labeled<L, V>)> for LabeledMap<K, L, V> {
+    fn from_iter<I: IntoIterator<Item = (K, Labeled<L, V>)>>(iter: I) -> Self {
+        LabeledMap { entries: iter.into_iter().collect() }
+    }
+}
+
+impl<K: Ord, L, V> Extend<(K, Labeled<L, V>)> for LabeledMap<K, L, V> {
+    fn extend<I: IntoIterator<Item = (K, Labeled<L, V>)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+impl<K, L, V> IntoIterator for LabeledMap<K, L, V> {
+    type Item = (K, Labeled<L, V>);
+    type IntoIter = btree_map::IntoIter<K, Labeled<L, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, K, L, V> IntoIterator for &'a LabeledMap<K, L, V> {
+    type Item = (&'a K, &'a Labeled<L, V>);
+    type IntoIter = btree_map::Iter<'a, K, Labeled<L, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    use crate::dclabel::DCLabel;
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    fn rows() -> LabeledVec<DCLabel, &'static str> {
+        vec![
+            Labeled::new(DCLabel::public(), "announcement"),
+            Labeled::new(label("<alice, True>"), "alice's note"),
+            Labeled::new(label("<bob, True>"), "bob's note"),
+        ].into_iter().collect()
+    }
+
+    #[test]
+    fn vec_visible_to() {
+        let rows = rows();
+        let clearance = label("<alice, True>");
+        let mut ctx = Context::new(DCLabel::public(), clearance.clone()).unwrap();
+        let visible: Vec<_> = rows.visible_to(&clearance).map(|r| ctx.unlabel(r).unwrap()).collect();
+        assert_eq!(visible, vec!["announcement", "alice's note"]);
+        assert_eq!(ctx.label(), &clearance);
+        assert_eq!(rows.visible_to(&DCLabel::public()).count(), 1);
+    }
+
+    #[test]
+    fn vec_label_upper_bound() {
+        assert_eq!(rows().label_upper_bound(), Some(label("<alice /\\ bob, True>")));
+        assert_eq!(LabeledVec::<DCLabel, ()>::new().label_upper_bound(), None);
+    }
+
+    #[test]
+    fn map_visible_to() {
+        let mut salaries = LabeledMap::new();
+        salaries.insert("alice", Labeled::new(label("<alice, True>"), 100));
+        salaries.insert("bob", Labeled::new(label("<bob, True>"), 90));
+        salaries.insert("intern", Labeled::new(DCLabel::public(), 10));
+
+        let keys: Vec<_> = salaries.visible_to(&label("<bob, True>")).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec!["bob", "intern"]);
+        assert_eq!(salaries.label_upper_bound(), Some(label("<alice /\\ bob, True>")));
+
+        salaries.remove(&"alice");
+        assert_eq!(salaries.label_upper_bound(), Some(label("<bob, True>")));
+        assert_eq!(salaries.len(), 2);
+    }
+}
@@ -0,0 +1,170 @@
+use core::fmt;
+
+use super::Context;
+use crate::Label;
+use crate::error::Result;
+use crate::labeled::Labeled;
+
+/// An error labeled with the label in effect where it was thrown, after LIO's exceptions.
+///
+/// Whether an error happened can depend on everything the computation has observed, so the
+/// error is only readable through [`Context::catch_labeled`], which taints the catcher with its
+/// label.
+pub struct LabeledError<L, E> {
+    label: L,
+    error: E,
+}
+
+impl<L, E> LabeledError<L, E> {
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    /// The error as a labeled value, to be unlabeled later, or never, without tainting the
+    /// computation now.
+    pub fn into_labeled(self) -> Labeled<L, E> {
+        Labeled::new(self.label, self.error)
+    }
+}
+
+impl<L: fmt::Debug, E> fmt::Debug for LabeledError<L, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LabeledError({:?}, <hidden>)", self.label)
+    }
+}
+
+impl<L: fmt::Debug, E> fmt::Display for LabeledError<L, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error labeled {:?}", self.label)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: fmt::Debug, E> std::error::Error for LabeledError<L, E> {}
+
+/// Throwing the error of a `Result` in a context, so that `?` propagates it as a
+/// [`LabeledError`].
+pub trait Throw<T, E> {
+    /// Label the error, if any, as by [`Context::throw`].
+    fn throw<L: Label + Clone>(self, ctx: &Context<L>) -> core::result::Result<T, LabeledError<L, E>>;
+}
+
+impl<T, E> Throw<T, E> for core::result::Result<T, E> {
+    fn throw<L: Label + Clone>(self, ctx: &Context<L>) -> core::result::Result<T, LabeledError<L, E>> {
+        self.map_err(|error| ctx.throw(error))
+    }
+}
+
+/// Labeled errors.
+impl<L: Label + Clone> Context<L> {
+    /// Label `error` with the current label joined with the PC label, the label of the fact that
+    /// it is being thrown.
+    pub fn throw<E>(&self, error: E) -> LabeledError<L, E> {
+        LabeledError { label: self.write_label(), error }
+    }
+
+    /// Run `f` in a copy of this context, catching the [`LabeledError`] it returns, if any.
+    ///
+    /// What `f` observes does not taint this context. A successful result is returned labeled
+    /// with the label of writes in the copy when `f` returns, so reading it taints the reader with
+    /// everything `f` observed. Catching an error taints the current label with the label of the
+    /// error. If that would exceed the clearance, the error cannot be caught and this fails with
+    /// [`Error::ClearanceViolation`](crate::Error::ClearanceViolation) instead.
+    pub fn catch_labeled<R, E, F>(&mut self, f: F) -> Result<core::result::Result<Labeled<L, R>, E>>
+    where
+        F: FnOnce(&mut Self) -> core::result::Result<R, LabeledError<L, E>>,
+    {
+        let mut inner = self.clone();
+        match f(&mut inner) {
+            Ok(result) => Ok(Ok(Labeled::new(inner.write_label(), result))),
+            Err(LabeledError { label, error }) => {
+                self.taint(&label)?;
+                Ok(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::twolevel::TwoLevel;
+
+    #[derive(Debug, PartialEq)]
+    struct NotFound;
+
+    #[derive(Debug, PartialEq)]
+    enum LookupError {
+        Label(Error),
+        NotFound,
+    }
+
+    /// Fails depending on `secret`.
+    fn lookup(
+        ctx: &mut Context<TwoLevel>, secret: &Labeled<TwoLevel, u32>,
+    ) -> core::result::Result<u32, LabeledError<TwoLevel, LookupError>> {
+        let n = ctx.unlabel(secret).map_err(LookupError::Label).throw(ctx)?;
+        if n == 0 {
+            return Err(ctx.throw(LookupError::NotFound));
+        }
+        Ok(n)
+    }
+
+    #[test]
+    fn catch_taints() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let secret = ctx.label_value(TwoLevel::High, 0).unwrap();
+        let caught = ctx.catch_labeled(|ctx| lookup(ctx, &secret)).map(core::result::Result::err);
+        assert_eq!(caught, Ok(Some(LookupError::NotFound)));
+        assert_eq!(ctx.label(), &TwoLevel::High);
+
+        let mut low = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        let caught = low.catch_labeled(|ctx| lookup(ctx, &secret)).map(core::result::Result::err);
+        assert_eq!(caught, Ok(Some(LookupError::Label(Error::ClearanceViolation))));
+        assert_eq!(low.label(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn success_is_labeled() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let secret = ctx.label_value(TwoLevel::High, 5).unwrap();
+        let n = ctx.catch_labeled(|ctx| lookup(ctx, &secret)).unwrap().unwrap();
+        assert_eq!(n.label(), &TwoLevel::High);
+        assert_eq!(ctx.label(), &TwoLevel::Low);
+        assert_eq!(ctx.unlabel(&n), Ok(5));
+        assert_eq!(ctx.label(), &TwoLevel::High);
+
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let one = ctx.catch_labeled(|_| Ok::<_, LabeledError<_, NotFound>>(1)).unwrap().unwrap();
+        assert_eq!(one.label(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn error_thrown_in_branch() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let secret = ctx.label_value(TwoLevel::High, true).unwrap();
+        let thrown = ctx.branch_on(&secret, |ctx| ctx.throw(NotFound), |_| unreachable!()).unwrap();
+        assert_eq!(ctx.label(), &TwoLevel::Low);
+        assert_eq!(thrown.value().label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn error_above_clearance() {
+        let thrower = Context::new(TwoLevel::High, TwoLevel::High).unwrap();
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        let result = ctx.catch_labeled(|_| Err::<(), _>(thrower.throw(NotFound)));
+        assert_eq!(result.err(), Some(Error::ClearanceViolation));
+        assert_eq!(ctx.label(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn into_labeled_defers_taint() {
+        let thrower = Context::new(TwoLevel::High, TwoLevel::High).unwrap();
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let labeled = thrower.throw(NotFound).into_labeled();
+        assert_eq!(labeled.label(), &TwoLevel::High);
+        assert_eq!(ctx.label(), &TwoLevel::Low);
+        assert_eq!(ctx.taint(labeled.label()), Ok(()));
+    }
+}
@@ -0,0 +1,394 @@
+//! The implicit current context.
+//!
+//! Rather than passing a [`Context`] to every operation, a context can be installed as the
+//! _current_ context for its label type with [`enter`]. The functions in this module then operate
+//! on it, following the same rules as the corresponding `Context` methods. Each label type has
+//! its own slot, so a `Context<DCLabel>` and a `Context<TwoLevel>` can be current at once.
+//!
+//! The current context is kept per thread. A [`LabeledFuture`](super::LabeledFuture) installs
+//! its context only while it is being polled, which makes the current context task-local for
+//! async code regardless of the executor.
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::Context;
+use crate::Label;
+use crate::error::{Error, Result};
+use crate::labeled::Labeled;
+
+thread_local! {
+    static CURRENT: RefCell<HashMap<TypeId, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Install `ctx` as the current context for `L` in place of whatever was there before.
+fn replace<L: 'static>(ctx: Option<Context<L>>) -> Option<Context<L>> {
+    CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        let old = match ctx {
+            Some(ctx) => current.insert(TypeId::of::<L>(), Box::new(ctx)),
+            None => current.remove(&TypeId::of::<L>()),
+        };
+        old.map(|old| *old.downcast::<Context<L>>().expect("context slot holds wrong label type"))
+    })
+}
+
+/// Reinstate `prev` as the current context and return the context being left. If `raise` is set,
+/// the label of `prev` is first raised by that of the context being left.
+fn leave<L: Label + Clone + 'static>(mut prev: Option<Context<L>>, raise: bool) -> Option<Context<L>> {
+    let left = replace::<L>(None);
+    if let (true, Some(prev), Some(left)) = (raise, &mut prev, &left) {
+        prev.label = prev.label.join(left.label());
+    }
+    replace(prev);
+    left
+}
+
+/// Reinstates the previously current context when dropped, including on unwinding, as by
+/// [`leave`].
+struct Restore<L: Label + Clone + 'static> {
+    prev: Option<Option<Context<L>>>,
+    raise: bool,
+}
+
+impl<L: Label + Clone + 'static> Drop for Restore<L> {
+    fn drop(&mut self) {
+        if let Some(prev) = self.prev.take() {
+            leave(prev, self.raise);
+        }
+    }
+}
+
+/// Run `f` with `ctx` as the current context, and reinstate the previously current context
+/// afterwards, raising its label by the final label of `ctx` if `raise` is set.
+fn run<L, R, F>(ctx: Context<L>, raise: bool, f: F) -> (R, Context<L>)
+where
+    L: Label + Clone + 'static,
+    F: FnOnce() -> R,
+{
+    let mut restore = Restore { prev: Some(replace(Some(ctx))), raise };
+    let result = f();
+    let prev = restore.prev.take().expect("context already restored");
+    let ctx = leave(prev, raise).expect("current context removed while entered");
+    (result, ctx)
+}
+
+/// Run `f` with `ctx` as the only current context, as if on a thread of its own, and reinstate
+/// the previously current context afterwards, unchanged.
+pub(crate) fn detach<L, R, F>(ctx: Context<L>, f: F) -> (R, Context<L>)
+where
+    L: Label + Clone + 'static,
+    F: FnOnce() -> R,
+{
+    run(ctx, false, f)
+}
+
+/// Run `f` with `ctx` as the current context, returning its result and the context as `f` left
+/// it. The previously current context, if any, is restored afterwards.
+///
+/// Entering a context while another is current must not let `f` do anything the current context
+/// could not. This fails with [`Error::FlowViolation`] unless the current label can flow to the
+/// label of `ctx`, and with [`Error::ClearanceViolation`] unless the clearance of `ctx` can flow
+/// to the current clearance. The current PC label carries over into `ctx` while `f` runs, and
+/// entering fails with [`Error::FlowViolation`] if raising the current label to that of `ctx`
+/// would not be allowed by [`Context::taint`].
+///
+/// The result of `f` may depend on anything `f` observed, so when `f` returns, the label of the
+/// previously current context is raised by the final label of `ctx`.
+pub fn enter<L, R, F>(mut ctx: Context<L>, f: F) -> Result<(R, Context<L>)>
+where
+    L: Label + Clone + 'static,
+    F: FnOnce() -> R,
+{
+    let pc = ctx.pc.clone();
+    match get_context::<L>() {
+        Ok(outer) => {
+            if !outer.label().can_flow_to(ctx.label()) {
+                return Err(Error::FlowViolation);
+            }
+            if !ctx.clearance().can_flow_to(outer.clearance()) {
+                return Err(Error::ClearanceViolation);
+            }
+            if let Some(pc) = outer.pc() {
+                if !pc.can_flow_to(outer.label()) && !ctx.label().can_flow_to(outer.label()) {
+                    return Err(Error::FlowViolation);
+                }
+            }
+            ctx.pc = match (ctx.pc, outer.pc) {
+                (Some(pc), Some(outer)) => Some(pc.join(&outer)),
+                (pc, outer) => pc.or(outer),
+            };
+        }
+        Err(Error::NoContext) => {}
+        Err(e) => return Err(e),
+    }
+    let (result, mut ctx) = run(ctx, true, f);
+    ctx.pc = pc;
+    Ok((result, ctx))
+}
+
+fn with<L: 'static, R, F: FnOnce(&mut Context<L>) -> Result<R>>(f: F) -> Result<R> {
+    CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        match current.get_mut(&TypeId::of::<L>()) {
+            Some(ctx) => f(ctx.downcast_mut().expect("context slot holds wrong label type")),
+            None => Err(Error::NoContext),
+        }
+    })
+}
+
+/// A copy of the current context.
+pub fn get_context<L: Label + Clone + 'static>() -> Result<Context<L>> {
+    with(|ctx: &mut Context<L>| Ok(ctx.clone()))
+}
+
+/// The current label.
+pub fn get_label<L: Label + Clone + 'static>() -> Result<L> {
+    with(|ctx: &mut Context<L>| Ok(ctx.label().clone()))
+}
+
+/// The current clearance.
+pub fn get_clearance<L: Label + Clone + 'static>() -> Result<L> {
+    with(|ctx: &mut Context<L>| Ok(ctx.clearance().clone()))
+}
+
+/// See [`Context::lower_clearance`].
+pub fn lower_clearance<L: Label + Clone + 'static>(clearance: L) -> Result<()> {
+    with(|ctx: &mut Context<L>| ctx.lower_clearance(clearance))
+}
+
+/// Restores the clearance of the current context when dropped, including on unwinding.
+struct RestoreClearance<L: Label + Clone + 'static>(Option<L>);
+
+impl<L: Label + Clone + 'static> Drop for RestoreClearance<L> {
+    fn drop(&mut self) {
+        if let Some(clearance) = self.0.take() {
+            let _ = with(|ctx: &mut Context<L>| {
+                ctx.clearance = clearance;
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Run `f` with the current clearance lowered to `clearance`, and restore it afterwards. See
+/// [`Context::with_clearance`].
+pub fn with_clearance<L: Label + Clone + 'static, R, F: FnOnce() -> R>(clearance: L, f: F) -> Result<R> {
+    let saved = with(|ctx: &mut Context<L>| {
+        let saved = ctx.clearance().clone();
+        ctx.lower_clearance(clearance)?;
+        Ok(saved)
+    })?;
+    let _restore = RestoreClearance(Some(saved));
+    Ok(f())
+}
+
+/// Restores the PC label of the current context when dropped, including on unwinding.
+struct RestorePc<L: Label + Clone + 'static>(Option<Option<L>>);
+
+impl<L: Label + Clone + 'static> Drop for RestorePc<L> {
+    fn drop(&mut self) {
+        if let Some(pc) = self.0.take() {
+            let _ = with(|ctx: &mut Context<L>| {
+                ctx.pc = pc;
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Run `f` with the current PC label raised by `label`. See [`Context::raise_pc`].
+pub fn raise_pc<L: Label + Clone + 'static, R, F: FnOnce() -> R>(label: &L, f: F) -> Result<Labeled<L, R>> {
+    let saved = with(|ctx: &mut Context<L>| {
+        if !label.can_flow_to(ctx.clearance()) {
+            return Err(Error::ClearanceViolation);
+        }
+        let raised = match &ctx.pc {
+            Some(pc) => pc.join(label),
+            None => label.clone(),
+        };
+        Ok(ctx.pc.replace(raised))
+    })?;
+    let restore = RestorePc(Some(saved));
+    let result = f();
+    let result_label = with(|ctx: &mut Context<L>| Ok(ctx.write_label()));
+    drop(restore);
+    Ok(Labeled::new(result_label?, result))
+}
+
+/// Run `then` or `otherwise` depending on `cond`, with the current PC label raised by the label
+/// of `cond`. See [`Context::branch_on`].
+pub fn branch_on<L, R, T, E>(cond: &Labeled<L, bool>, then: T, otherwise: E) -> Result<Labeled<L, R>>
+where
+    L: Label + Clone + 'static,
+    T: FnOnce() -> R,
+    E: FnOnce() -> R,
+{
+    let cond_value = *cond.value();
+    raise_pc(cond.label(), || if cond_value { then() } else { otherwise() })
+}
+
+/// See [`Context::taint`].
+pub fn taint<L: Label + Clone + 'static>(label: &L) -> Result<()> {
+    with(|ctx: &mut Context<L>| ctx.taint(label))
+}
+
+/// See [`Context::guard_write`].
+pub fn guard_write<L: Label + Clone + 'static>(label: &L) -> Result<()> {
+    with(|ctx: &mut Context<L>| ctx.guard_write(label))
+}
+
+/// See [`Context::label_value`].
+pub fn label<L: Label + Clone + 'static, T>(label: L, value: T) -> Result<Labeled<L, T>> {
+    with(|ctx: &mut Context<L>| ctx.label_value(label, value))
+}
+
+/// See [`Context::unlabel`].
+pub fn unlabel<L: Label + Clone + 'static, T: Clone>(labeled: &Labeled<L, T>) -> Result<T> {
+    with(|ctx: &mut Context<L>| ctx.unlabel(labeled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twolevel::TwoLevel;
+
+    #[test]
+    fn no_context() {
+        assert_eq!(get_label::<TwoLevel>(), Err(Error::NoContext));
+    }
+
+    #[test]
+    fn enter_restores() {
+        let outer = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let ((), outer) = enter(outer, || {
+            let inner = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+            let ((), inner) = enter(inner, || taint(&TwoLevel::High).unwrap()).unwrap();
+            assert_eq!(inner.label(), &TwoLevel::High);
+            assert_eq!(get_label(), Ok(TwoLevel::High));
+        }).unwrap();
+        assert_eq!(outer.label(), &TwoLevel::High);
+        assert_eq!(get_label::<TwoLevel>(), Err(Error::NoContext));
+    }
+
+    #[test]
+    fn enter_restores_on_panic() {
+        let ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let result = std::panic::catch_unwind(|| enter(ctx, || panic!("boom")));
+        assert!(result.is_err());
+        assert_eq!(get_label::<TwoLevel>(), Err(Error::NoContext));
+    }
+
+    #[test]
+    fn enter_raises_outer_label_on_panic() {
+        let outer = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let ((), outer) = enter(outer, || {
+            let secret = label(TwoLevel::High, 7).unwrap();
+            let inner = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+            let leaked = std::panic::catch_unwind(|| {
+                enter(inner, || if unlabel(&secret).unwrap() == 7 { panic!("seven") })
+            });
+            assert!(leaked.is_err());
+            assert_eq!(guard_write(&TwoLevel::Low), Err(Error::FlowViolation));
+        }).unwrap();
+        assert_eq!(outer.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn scoped_clearance() {
+        let ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let ((), ctx) = enter(ctx, || {
+            let secret = label(TwoLevel::High, 7).unwrap();
+            let inner = with_clearance(TwoLevel::Low, || {
+                assert_eq!(get_clearance(), Ok(TwoLevel::Low));
+                assert_eq!(lower_clearance(TwoLevel::High), Err(Error::ClearanceViolation));
+                unlabel(&secret)
+            });
+            assert_eq!(inner, Ok(Err(Error::ClearanceViolation)));
+            assert_eq!(get_clearance(), Ok(TwoLevel::High));
+
+            let panicked = std::panic::catch_unwind(|| {
+                with_clearance(TwoLevel::Low, || panic!("untrusted code panicked"))
+            });
+            assert!(panicked.is_err());
+            assert_eq!(get_clearance(), Ok(TwoLevel::High));
+            lower_clearance(TwoLevel::Low).unwrap();
+        }).unwrap();
+        assert_eq!(ctx.clearance(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn nested_enter_cannot_raise_clearance() {
+        let ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let ((), _) = enter(ctx, || {
+            let secret = label(TwoLevel::High, 7).unwrap();
+            let inner = with_clearance(TwoLevel::Low, || {
+                let raised = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+                enter(raised, || unlabel(&secret)).map(|(r, _)| r)
+            });
+            assert_eq!(inner, Ok(Err(Error::ClearanceViolation)));
+            assert_eq!(get_label(), Ok(TwoLevel::Low));
+        }).unwrap();
+    }
+
+    #[test]
+    fn implicit_flows() {
+        let ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let ((), ctx) = enter(ctx, || {
+            let secret = label(TwoLevel::High, false).unwrap();
+            let written = branch_on(&secret, || unreachable!(), || label(TwoLevel::Low, 0).map(|_| ())).unwrap();
+            assert_eq!(written.label(), &TwoLevel::High);
+            assert_eq!(written.value(), &Err(Error::FlowViolation));
+            let panicked = std::panic::catch_unwind(|| raise_pc(&TwoLevel::High, || panic!("boom")));
+            assert!(panicked.is_err());
+            assert_eq!(guard_write(&TwoLevel::Low), Ok(()));
+        }).unwrap();
+        assert_eq!(ctx.pc(), None);
+    }
+
+    #[test]
+    fn same_rules_as_context() {
+        let ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let ((), ctx) = enter(ctx, || {
+            let secret = label(TwoLevel::High, 7).unwrap();
+            assert_eq!(unlabel(&secret), Ok(7));
+            assert_eq!(guard_write(&TwoLevel::Low), Err(Error::FlowViolation));
+            assert_eq!(get_clearance(), Ok(TwoLevel::High));
+        }).unwrap();
+        assert_eq!(ctx.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn nested_enter_cannot_escape() {
+        let ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let ((), _) = enter(ctx, || {
+            taint(&TwoLevel::High).unwrap();
+            let escape = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+            let result = enter(escape, || guard_write(&TwoLevel::Low));
+            assert_eq!(result.map(|(r, _)| r), Err(Error::FlowViolation));
+
+            raise_pc(&TwoLevel::High, || {
+                let inner = Context::new(TwoLevel::High, TwoLevel::High).unwrap();
+                let (pc, inner) = enter(inner, || get_context().map(|ctx| ctx.pc().cloned())).unwrap();
+                assert_eq!(pc, Ok(Some(TwoLevel::High)));
+                assert_eq!(inner.pc(), None);
+            }).unwrap();
+        }).unwrap();
+
+        let ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let ((), _) = enter(ctx, || {
+            raise_pc(&TwoLevel::High, || {
+                let upgraded = Context::new(TwoLevel::High, TwoLevel::High).unwrap();
+                assert_eq!(enter(upgraded, || ()).map(|_| ()), Err(Error::FlowViolation));
+            }).unwrap();
+            assert_eq!(get_label(), Ok(TwoLevel::Low));
+        }).unwrap();
+
+        let ctx = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        let ((), _) = enter(ctx, || {
+            let wider = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+            assert_eq!(enter(wider, || ()).map(|_| ()), Err(Error::ClearanceViolation));
+        }).unwrap();
+    }
+}
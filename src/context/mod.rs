@@ -0,0 +1,219 @@
+use crate::Label;
+use crate::error::{Error, Result};
+use crate::labeled::Labeled;
+
+#[cfg(feature = "std")]
+pub mod current;
+mod catch;
+mod pc;
+#[cfg(feature = "std")]
+mod privileged;
+#[cfg(feature = "std")]
+mod task;
+#[cfg(feature = "std")]
+mod thread;
+
+pub use catch::{LabeledError, Throw};
+#[cfg(feature = "std")]
+pub use task::LabeledFuture;
+#[cfg(feature = "std")]
+pub use thread::{spawn_labeled, LabeledJoinHandle};
+
+/// The floating label of a computation.
+///
+/// A `Context` tracks the _current label_, an upper bound on everything the computation has
+/// observed so far, and the _clearance_, an upper bound on what it may ever observe. Reads raise
+/// the current label (using [`Label::join`]), and writes are only permitted to objects whose
+/// label the current label can flow to. The current label is never lowered.
+///
+/// Inside a branch on labeled data (see [`Context::branch_on`]) the context also has a _PC
+/// label_, which writes must flow from in addition to the current label.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Context<L> {
+    label: L,
+    clearance: L,
+    pc: Option<L>,
+}
+
+impl<L: Label + Clone> Context<L> {
+    /// Create a context with the given current label and clearance.
+    ///
+    /// Fails with [`Error::ClearanceViolation`] if `label` cannot flow to `clearance`.
+    pub fn new(label: L, clearance: L) -> Result<Self> {
+        if !label.can_flow_to(&clearance) {
+            return Err(Error::ClearanceViolation);
+        }
+        Ok(Context { label, clearance, pc: None })
+    }
+
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    pub fn clearance(&self) -> &L {
+        &self.clearance
+    }
+
+    /// Raise the current label to account for observing data labeled `label`.
+    ///
+    /// The current label becomes the join of itself and `label`. Fails, leaving the context
+    /// unchanged, if the result would not flow to the clearance.
+    ///
+    /// Inside a branch, raising the current label would reveal which way the branch went, so
+    /// this also fails with [`Error::FlowViolation`] if the label would change while the PC
+    /// label cannot flow to the current label.
+    pub fn taint(&mut self, label: &L) -> Result<()> {
+        let raised = self.label.join(label);
+        if !raised.can_flow_to(&self.clearance) {
+            return Err(Error::ClearanceViolation);
+        }
+        if let Some(pc) = &self.pc {
+            if !pc.can_flow_to(&self.label) && !label.can_flow_to(&self.label) {
+                return Err(Error::FlowViolation);
+            }
+        }
+        self.label = raised;
+        Ok(())
+    }
+
+    /// Check that the computation may write to an object labeled `label`.
+    ///
+    /// Writes are allowed only if the current label and the PC label can flow to `label` and
+    /// `label` can flow to the clearance.
+    pub fn guard_write(&self, label: &L) -> Result<()> {
+        if !label.can_flow_to(&self.clearance) {
+            return Err(Error::ClearanceViolation);
+        }
+        if !self.write_label().can_flow_to(label) {
+            return Err(Error::FlowViolation);
+        }
+        Ok(())
+    }
+
+    /// Lower the clearance to `clearance` for the rest of the computation.
+    ///
+    /// Fails with [`Error::ClearanceViolation`], leaving the context unchanged, unless
+    /// `clearance` can flow to the current clearance and the current label can flow to
+    /// `clearance`.
+    pub fn lower_clearance(&mut self, clearance: L) -> Result<()> {
+        if !clearance.can_flow_to(&self.clearance) || !self.label.can_flow_to(&clearance) {
+            return Err(Error::ClearanceViolation);
+        }
+        self.clearance = clearance;
+        Ok(())
+    }
+
+    /// Run `f` with the clearance lowered to `clearance`, as by [`Context::lower_clearance`],
+    /// and restore the previous clearance afterwards.
+    ///
+    /// The current label is not restored: anything `f` observed stays reflected in it.
+    pub fn with_clearance<R, F: FnOnce(&mut Self) -> R>(&mut self, clearance: L, f: F) -> Result<R> {
+        let saved = self.clearance.clone();
+        self.lower_clearance(clearance)?;
+        let restore = RestoreClearance { ctx: self, clearance: Some(saved) };
+        Ok(f(&mut *restore.ctx))
+    }
+
+    /// Protect `value` with `label`, which must satisfy [`Context::guard_write`].
+    pub fn label_value<T>(&self, label: L, value: T) -> Result<Labeled<L, T>> {
+        self.guard_write(&label)?;
+        Ok(Labeled::new(label, value))
+    }
+
+    /// Read the value of a labeled value, tainting the current label with its label.
+    pub fn unlabel<T: Clone>(&mut self, labeled: &Labeled<L, T>) -> Result<T> {
+        self.taint(labeled.label())?;
+        Ok(labeled.value().clone())
+    }
+}
+
+/// Restores the clearance of a context when dropped, including on unwinding.
+struct RestoreClearance<'a, L> {
+    ctx: &'a mut Context<L>,
+    clearance: Option<L>,
+}
+
+impl<L> Drop for RestoreClearance<'_, L> {
+    fn drop(&mut self) {
+        if let Some(clearance) = self.clearance.take() {
+            self.ctx.clearance = clearance;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twolevel::TwoLevel;
+
+    #[test]
+    fn label_above_clearance() {
+        assert_eq!(Context::new(TwoLevel::High, TwoLevel::Low), Err(Error::ClearanceViolation));
+    }
+
+    #[test]
+    fn taint_raises_label() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        ctx.taint(&TwoLevel::High).unwrap();
+        assert_eq!(ctx.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn taint_above_clearance() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        assert_eq!(ctx.taint(&TwoLevel::High), Err(Error::ClearanceViolation));
+        assert_eq!(ctx.label(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn high_cannot_write_low() {
+        let ctx = Context::new(TwoLevel::High, TwoLevel::High).unwrap();
+        assert_eq!(ctx.guard_write(&TwoLevel::Low), Err(Error::FlowViolation));
+        assert_eq!(ctx.guard_write(&TwoLevel::High), Ok(()));
+    }
+
+    #[test]
+    fn lower_clearance() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        ctx.lower_clearance(TwoLevel::Low).unwrap();
+        assert_eq!(ctx.clearance(), &TwoLevel::Low);
+        assert_eq!(ctx.lower_clearance(TwoLevel::High), Err(Error::ClearanceViolation));
+        assert_eq!(ctx.taint(&TwoLevel::High), Err(Error::ClearanceViolation));
+    }
+
+    #[test]
+    fn clearance_below_label() {
+        let mut ctx = Context::new(TwoLevel::High, TwoLevel::High).unwrap();
+        assert_eq!(ctx.lower_clearance(TwoLevel::Low), Err(Error::ClearanceViolation));
+        assert_eq!(ctx.with_clearance(TwoLevel::Low, |_| ()), Err(Error::ClearanceViolation));
+        assert_eq!(ctx.clearance(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn with_clearance_restores() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let secret = ctx.label_value(TwoLevel::High, 1).unwrap();
+        let result = ctx.with_clearance(TwoLevel::Low, |untrusted| untrusted.unlabel(&secret));
+        assert_eq!(result, Ok(Err(Error::ClearanceViolation)));
+        assert_eq!(ctx.clearance(), &TwoLevel::High);
+        assert_eq!(ctx.unlabel(&secret), Ok(1));
+    }
+
+    #[test]
+    fn with_clearance_restores_on_panic() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            ctx.with_clearance(TwoLevel::Low, |_| panic!("untrusted code panicked"))
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(ctx.clearance(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn unlabel_taints() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let secret = ctx.label_value(TwoLevel::High, 42).unwrap();
+        assert_eq!(ctx.unlabel(&secret), Ok(42));
+        assert_eq!(ctx.label(), &TwoLevel::High);
+    }
+}
@@ -0,0 +1,168 @@
+use super::Context;
+use crate::Label;
+use crate::error::{Error, Result};
+use crate::labeled::Labeled;
+
+/// Implicit flows.
+///
+/// Branching on secret data leaks it through the writes made in the branch, even if the data is
+/// never copied. Code in a branch therefore runs with a raised _PC label_: every write made
+/// inside must flow from the condition's label as well as from the current label. Unlike the
+/// current label, the PC label is scoped, and drops back when the branch returns. Nested
+/// branches form a stack, the PC label being the join of the labels of all enclosing conditions.
+///
+/// For the same reason the current label cannot be raised inside a branch whose PC label does
+/// not already flow to it: see [`Context::taint`].
+impl<L: Label + Clone> Context<L> {
+    /// The PC label, or `None` outside any branch.
+    pub fn pc(&self) -> Option<&L> {
+        self.pc.as_ref()
+    }
+
+    /// The label writes must flow from: the current label joined with the PC label.
+    pub(crate) fn write_label(&self) -> L {
+        match &self.pc {
+            Some(pc) => self.label.join(pc),
+            None => self.label.clone(),
+        }
+    }
+
+    /// Run `f` with the PC label raised by `label`, which must flow to the clearance.
+    ///
+    /// The result of `f` depends on data labeled `label`, so it is returned labeled with the
+    /// label of writes made inside the branch.
+    pub fn raise_pc<R, F: FnOnce(&mut Self) -> R>(&mut self, label: &L, f: F) -> Result<Labeled<L, R>> {
+        if !label.can_flow_to(&self.clearance) {
+            return Err(Error::ClearanceViolation);
+        }
+        let raised = match &self.pc {
+            Some(pc) => pc.join(label),
+            None => label.clone(),
+        };
+        let saved = self.pc.replace(raised);
+        let restore = RestorePc { ctx: self, pc: Some(saved) };
+        let result = f(&mut *restore.ctx);
+        let result_label = restore.ctx.write_label();
+        Ok(Labeled::new(result_label, result))
+    }
+
+    /// Run `then` or `otherwise` depending on `cond`, with the PC label raised by the label of
+    /// `cond` as for [`Context::raise_pc`].
+    pub fn branch_on<R, T, E>(&mut self, cond: &Labeled<L, bool>, then: T, otherwise: E) -> Result<Labeled<L, R>>
+    where
+        T: FnOnce(&mut Self) -> R,
+        E: FnOnce(&mut Self) -> R,
+    {
+        let cond_value = *cond.value();
+        self.raise_pc(cond.label(), |ctx| if cond_value { then(ctx) } else { otherwise(ctx) })
+    }
+}
+
+/// Restores the PC label of a context when dropped, including on unwinding.
+struct RestorePc<'a, L> {
+    ctx: &'a mut Context<L>,
+    pc: Option<Option<L>>,
+}
+
+impl<L> Drop for RestorePc<'_, L> {
+    fn drop(&mut self) {
+        if let Some(pc) = self.pc.take() {
+            self.ctx.pc = pc;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use crate::dclabel::DCLabel;
+    use crate::twolevel::TwoLevel;
+
+    #[test]
+    fn branch_cannot_write_low() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let secret = ctx.label_value(TwoLevel::High, true).unwrap();
+        let result = ctx.branch_on(
+            &secret,
+            |ctx| ctx.guard_write(&TwoLevel::Low),
+            |_| unreachable!(),
+        ).unwrap();
+        assert_eq!(result.label(), &TwoLevel::High);
+        assert_eq!(result.value(), &Err(Error::FlowViolation));
+
+        // The PC label is popped after the branch, and the current label was never raised.
+        assert_eq!(ctx.pc(), None);
+        assert_eq!(ctx.label(), &TwoLevel::Low);
+        assert_eq!(ctx.guard_write(&TwoLevel::Low), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn nested_branches() {
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let alice: DCLabel = "<alice, True>".parse().unwrap();
+        let bob: DCLabel = "<bob, True>".parse().unwrap();
+        let a = ctx.label_value(alice.clone(), false).unwrap();
+        let b = ctx.label_value(bob.clone(), true).unwrap();
+
+        let result = ctx.branch_on(&a, |_| unreachable!(), |ctx| {
+            ctx.branch_on(&b, |ctx| {
+                assert_eq!(ctx.pc(), Some(&alice.join(&bob)));
+                assert_eq!(ctx.guard_write(&alice), Err(Error::FlowViolation));
+                ctx.guard_write(&alice.join(&bob))
+            }, |_| unreachable!()).unwrap()
+        }).unwrap();
+        assert_eq!(result.label(), &alice);
+        assert_eq!(result.value().label(), &alice.join(&bob));
+        assert_eq!(result.value().value(), &Ok(()));
+        assert_eq!(ctx.pc(), None);
+    }
+
+    #[test]
+    fn condition_above_clearance() {
+        let writer = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let secret = writer.label_value(TwoLevel::High, true).unwrap();
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        assert_eq!(ctx.branch_on(&secret, |_| (), |_| ()).err(), Some(Error::ClearanceViolation));
+    }
+
+    #[test]
+    fn raise_pc_restores_on_panic() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            ctx.raise_pc(&TwoLevel::High, |_| panic!("branch panicked"))
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(ctx.pc(), None);
+        assert_eq!(ctx.guard_write(&TwoLevel::Low), Ok(()));
+    }
+
+    #[test]
+    fn result_carries_current_label() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let public = ctx.label_value(TwoLevel::Low, true).unwrap();
+        let secret = ctx.label_value(TwoLevel::High, 3).unwrap();
+        let result = ctx.branch_on(&public, |ctx| ctx.unlabel(&secret).unwrap(), |_| 0).unwrap();
+        assert_eq!(result.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn no_sensitive_upgrade() {
+        let writer = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let outcomes: Vec<_> = [true, false].iter().map(|&value| {
+            let secret = writer.label_value(TwoLevel::High, value).unwrap();
+            let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+            let tainted = ctx.branch_on(&secret, |ctx| ctx.taint(&TwoLevel::High), |_| Ok(())).unwrap();
+            assert_eq!(tainted.label(), &TwoLevel::High);
+            (ctx.label().clone(), ctx.guard_write(&TwoLevel::Low))
+        }).collect();
+        assert_eq!(outcomes[0], outcomes[1]);
+        assert_eq!(outcomes[0], (TwoLevel::Low, Ok(())));
+
+        let mut ctx = Context::new(TwoLevel::High, TwoLevel::High).unwrap();
+        let secret = ctx.label_value(TwoLevel::High, true).unwrap();
+        let tainted = ctx.branch_on(&secret, |ctx| ctx.taint(&TwoLevel::High), |_| Ok(())).unwrap();
+        assert_eq!(tainted.value(), &Ok(()));
+    }
+}
@@ -0,0 +1,167 @@
+use super::Context;
+use crate::Label;
+use crate::audit::{AuditKind, AuditedPrivilege};
+use crate::dclabel::DCLabel;
+use crate::error::{Error, Result};
+use crate::labeled::Labeled;
+
+/// Privileged operations.
+///
+/// Each of these records an audit event, including the caller's `justification`, before taking
+/// effect, and fails with [`Error::AuditFailed`] without taking effect if it cannot be recorded.
+impl Context<DCLabel> {
+    /// Lower the current label to `label`.
+    ///
+    /// The current label must flow to `label` when exercising the privilege, and `label` must
+    /// flow to the clearance.
+    pub fn declassify(
+        &mut self, label: DCLabel, privilege: &AuditedPrivilege, justification: &str,
+    ) -> Result<()> {
+        if !label.can_flow_to(self.clearance()) {
+            return Err(Error::ClearanceViolation);
+        }
+        if !self.label().can_flow_to_with(&label, privilege.privilege()) {
+            return Err(Error::FlowViolation);
+        }
+        privilege.record(AuditKind::Declassify, self.label(), &label, justification)?;
+        self.label = label;
+        Ok(())
+    }
+
+    /// Check that the computation may write to an object labeled `label` when exercising the
+    /// privilege.
+    pub fn guard_write_with(
+        &self, label: &DCLabel, privilege: &AuditedPrivilege, justification: &str,
+    ) -> Result<()> {
+        if !label.can_flow_to(self.clearance()) {
+            return Err(Error::ClearanceViolation);
+        }
+        let write_label = self.write_label();
+        if !write_label.can_flow_to_with(label, privilege.privilege()) {
+            return Err(Error::FlowViolation);
+        }
+        privilege.record(AuditKind::Write, &write_label, label, justification)
+    }
+
+    /// Give `labeled` the new label `label`, which its current label must flow to when
+    /// exercising the privilege.
+    ///
+    /// The new label must also be writable by the computation using the privilege, as for
+    /// [`Context::guard_write_with`].
+    pub fn relabel<T>(
+        &self, labeled: Labeled<DCLabel, T>, label: DCLabel, privilege: &AuditedPrivilege, justification: &str,
+    ) -> Result<Labeled<DCLabel, T>> {
+        if !label.can_flow_to(self.clearance()) {
+            return Err(Error::ClearanceViolation);
+        }
+        if !self.write_label().can_flow_to_with(&label, privilege.privilege())
+            || !labeled.label().can_flow_to_with(&label, privilege.privilege())
+        {
+            return Err(Error::FlowViolation);
+        }
+        privilege.record(AuditKind::Relabel, labeled.label(), &label, justification)?;
+        Ok(Labeled::new(label, labeled.into_value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditSink, CallbackSink, JsonLinesSink, MemorySink};
+    use crate::dclabel::Privilege;
+    use std::io;
+    use std::sync::Arc;
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    fn audited(p: &str) -> (AuditedPrivilege, Arc<MemorySink>) {
+        let sink = Arc::new(MemorySink::new());
+        (AuditedPrivilege::new(Privilege::new(p), sink.clone()), sink)
+    }
+
+    #[test]
+    fn declassify_is_recorded() {
+        let (alice, sink) = audited("alice");
+        let mut ctx = Context::new(label("<alice /\\ bob, True>"), DCLabel::top()).unwrap();
+        ctx.declassify(label("<bob, True>"), &alice, "quarterly report").unwrap();
+        assert_eq!(ctx.label(), &label("<bob, True>"));
+
+        let events = sink.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, AuditKind::Declassify);
+        assert_eq!(events[0].old_label, label("<alice /\\ bob, True>"));
+        assert_eq!(events[0].new_label, label("<bob, True>"));
+        assert_eq!(&events[0].privilege, alice.privilege());
+        assert_eq!(events[0].justification, "quarterly report");
+    }
+
+    #[test]
+    fn insufficient_privilege_is_refused() {
+        let (alice, sink) = audited("alice");
+        let mut ctx = Context::new(label("<alice /\\ bob, True>"), DCLabel::top()).unwrap();
+        assert_eq!(ctx.declassify(DCLabel::public(), &alice, "oops"), Err(Error::FlowViolation));
+        assert_eq!(ctx.guard_write_with(&DCLabel::public(), &alice, "oops"), Err(Error::FlowViolation));
+        assert_eq!(ctx.label(), &label("<alice /\\ bob, True>"));
+        assert!(sink.events().is_empty());
+    }
+
+    #[test]
+    fn privileged_write_and_relabel() {
+        let (alice, sink) = audited("alice");
+        let ctx = Context::new(label("<alice, True>"), DCLabel::top()).unwrap();
+        assert_eq!(ctx.guard_write(&DCLabel::public()), Err(Error::FlowViolation));
+        ctx.guard_write_with(&DCLabel::public(), &alice, "publish").unwrap();
+
+        let secret = ctx.label_value(label("<alice, True>"), 3).unwrap();
+        let endorsed = ctx.relabel(secret, label("<alice, alice>"), &alice, "sign").unwrap();
+        assert_eq!(endorsed.label(), &label("<alice, alice>"));
+
+        let kinds: Vec<AuditKind> = sink.events().iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![AuditKind::Write, AuditKind::Relabel]);
+    }
+
+    #[test]
+    fn write_in_branch_records_pc() {
+        let (alice, sink) = audited("alice");
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let secret = ctx.label_value(label("<alice, True>"), true).unwrap();
+        let written = ctx.branch_on(&secret, |ctx| {
+            ctx.guard_write_with(&DCLabel::public(), &alice, "publish")
+        }, |_| unreachable!()).unwrap();
+        assert_eq!(written.value(), &Ok(()));
+        assert_eq!(sink.events()[0].old_label, label("<alice, True>"));
+    }
+
+    #[test]
+    fn failing_sink_blocks_declassification() {
+        let sink: Arc<dyn AuditSink> =
+            Arc::new(CallbackSink::new(|_: &_| Err(io::Error::other("disk full"))));
+        let alice = AuditedPrivilege::new(Privilege::new("alice"), sink);
+        let mut ctx = Context::new(label("<alice, True>"), DCLabel::top()).unwrap();
+        assert_eq!(
+            ctx.declassify(DCLabel::public(), &alice, "publish"),
+            Err(Error::AuditFailed("disk full".to_string()))
+        );
+        assert_eq!(ctx.label(), &label("<alice, True>"));
+    }
+
+    #[test]
+    fn json_lines_sink() {
+        let path = std::env::temp_dir().join(format!("label-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let sink = Arc::new(JsonLinesSink::open(&path).unwrap());
+        let alice = AuditedPrivilege::new(Privilege::new("alice"), sink);
+        let mut ctx = Context::new(label("<alice, True>"), DCLabel::top()).unwrap();
+        ctx.declassify(DCLabel::public(), &alice, "first").unwrap();
+        ctx.guard_write_with(&DCLabel::public(), &alice, "second").unwrap();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"kind\":\"declassify\",\"old_label\":\"<(alice), True>\""));
+        assert!(lines[1].contains("\"justification\":\"second\""));
+        std::fs::remove_file(path).unwrap();
+    }
+}
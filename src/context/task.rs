@@ -0,0 +1,234 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use super::{current, Context};
+use crate::Label;
+use crate::error::{Error, Result};
+use crate::labeled::Labeled;
+
+/// A future that runs with its own current context.
+///
+/// Every time it is polled, the wrapped future's context is installed as the
+/// [current](super::current) context, and taken back out when polling returns. The context
+/// therefore follows the future across `.await` points and between threads of a multi-threaded
+/// executor, while other tasks polled on the same thread see their own.
+///
+/// The output is labeled with the task's current label when it completes, so whoever observes
+/// the result is tainted by everything the task observed.
+///
+/// A future polled while another context is current, such as a child task being awaited by its
+/// parent, runs with that context set aside, as if it had been spawned. Since the parent
+/// observes the child's progress, it is tainted with the child's label around every poll, and
+/// its PC label carries over into the child. If the parent cannot be tainted, the future
+/// completes with the error instead of its output.
+pub struct LabeledFuture<L, F> {
+    ctx: Option<Context<L>>,
+    future: F,
+}
+
+impl<L: Label + Clone + 'static, F: Future> LabeledFuture<L, F> {
+    /// Run `future` with `ctx` as its current context.
+    pub fn new(ctx: Context<L>, future: F) -> Self {
+        LabeledFuture { ctx: Some(ctx), future }
+    }
+
+    /// Wrap `future` to run as a child of the current task, starting with a copy of its current
+    /// label and clearance.
+    ///
+    /// The child can be spawned on any executor, in which case the parent is only tainted by
+    /// what the child observed if it unlabels the result. Awaiting the child directly taints the
+    /// parent with its label.
+    pub fn fork(future: F) -> Result<Self> {
+        Ok(LabeledFuture::new(current::get_context()?, future))
+    }
+}
+
+impl<L: Label + Clone + 'static, F: Future> Future for LabeledFuture<L, F> {
+    type Output = Result<Labeled<L, F::Output>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // Safety: `future` is structurally pinned and never moved out of `self`; `ctx` is not
+        // pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        let mut ctx = this.ctx.take().expect("LabeledFuture polled after completion");
+        if let Err(e) = taint_parent(ctx.label()) {
+            return Poll::Ready(Err(e));
+        }
+        let pc = ctx.pc.clone();
+        if let Ok(parent) = current::get_context::<L>() {
+            ctx.pc = match (ctx.pc, parent.pc) {
+                (Some(pc), Some(parent)) => Some(pc.join(&parent)),
+                (pc, parent) => pc.or(parent),
+            };
+        }
+        let (poll, mut ctx) = current::detach(ctx, || future.poll(cx));
+        ctx.pc = pc;
+        if let Err(e) = taint_parent(ctx.label()) {
+            return Poll::Ready(Err(e));
+        }
+        match poll {
+            Poll::Ready(output) => Poll::Ready(Ok(Labeled::new(ctx.label().clone(), output))),
+            Poll::Pending => {
+                this.ctx = Some(ctx);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Taint the current context, if any, with `label`.
+fn taint_parent<L: Label + Clone + 'static>(label: &L) -> Result<()> {
+    match current::taint(label) {
+        Err(Error::NoContext) => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twolevel::TwoLevel;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Yields once before completing, so that tasks can be interleaved.
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                Poll::Pending
+            }
+        }
+    }
+
+    fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        let waker = Arc::new(Noop).into();
+        Pin::new(future).poll(&mut task::Context::from_waker(&waker))
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = poll(&mut future) {
+                return output;
+            }
+        }
+    }
+
+    fn low() -> Context<TwoLevel> {
+        Context::new(TwoLevel::Low, TwoLevel::High).unwrap()
+    }
+
+    #[test]
+    fn label_survives_await() {
+        let result = block_on(LabeledFuture::new(low(), async {
+            current::taint(&TwoLevel::High).unwrap();
+            YieldNow(false).await;
+            current::get_label::<TwoLevel>().unwrap()
+        })).unwrap();
+        assert_eq!(result.label(), &TwoLevel::High);
+        assert_eq!(low().unlabel(&result), Ok(TwoLevel::High));
+    }
+
+    #[test]
+    fn interleaved_tasks_are_isolated() {
+        let mut high = Box::pin(LabeledFuture::new(low(), async {
+            current::taint(&TwoLevel::High).unwrap();
+            YieldNow(false).await;
+            current::guard_write(&TwoLevel::Low)
+        }));
+        let mut low_task = Box::pin(LabeledFuture::new(low(), async {
+            YieldNow(false).await;
+            current::guard_write(&TwoLevel::Low)
+        }));
+
+        assert!(poll(&mut high).is_pending());
+        assert!(poll(&mut low_task).is_pending());
+        assert_eq!(current::get_label::<TwoLevel>(), Err(Error::NoContext));
+
+        match (poll(&mut high), poll(&mut low_task)) {
+            (Poll::Ready(Ok(h)), Poll::Ready(Ok(l))) => {
+                assert_eq!(low().unlabel(&h), Ok(Err(Error::FlowViolation)));
+                assert_eq!(low().unlabel(&l), Ok(Ok(())));
+            }
+            _ => panic!("tasks did not complete"),
+        }
+    }
+
+    #[test]
+    fn child_inherits_and_taints_parent() {
+        let result = block_on(LabeledFuture::new(low(), async {
+            let child = LabeledFuture::<TwoLevel, _>::fork(async {
+                assert_eq!(current::get_label(), Ok(TwoLevel::Low));
+                current::taint(&TwoLevel::High).unwrap();
+                5
+            }).unwrap();
+            let output = child.await.unwrap();
+            assert_eq!(current::get_label(), Ok(TwoLevel::High));
+            current::unlabel(&output).unwrap()
+        })).unwrap();
+        assert_eq!(result.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn parent_raised_before_awaiting_child() {
+        let result = block_on(LabeledFuture::new(low(), async {
+            let child = LabeledFuture::<TwoLevel, _>::fork(async {
+                YieldNow(false).await;
+                current::guard_write(&TwoLevel::Low)
+            }).unwrap();
+            current::taint(&TwoLevel::High).unwrap();
+            let written = child.await.unwrap();
+            assert_eq!(written.label(), &TwoLevel::Low);
+            current::unlabel(&written).unwrap()
+        })).unwrap();
+        assert_eq!(result.label(), &TwoLevel::High);
+        assert_eq!(result.value(), &Ok(()));
+    }
+
+    #[test]
+    fn child_runs_under_parent_pc() {
+        let result = block_on(LabeledFuture::new(low(), async {
+            let secret = current::label(TwoLevel::High, true).unwrap();
+            let child = LabeledFuture::<TwoLevel, _>::fork(async { current::guard_write(&TwoLevel::Low) }).unwrap();
+            let mut child = Box::pin(child);
+            let written = current::branch_on(&secret, || match poll(&mut child) {
+                Poll::Ready(Ok(written)) => written.value().clone(),
+                _ => panic!("child did not complete"),
+            }, || unreachable!()).unwrap();
+            current::unlabel(&written).unwrap()
+        })).unwrap();
+        assert_eq!(result.value(), &Err(Error::FlowViolation));
+    }
+
+    #[test]
+    fn parent_cleared_below_child() {
+        let result = block_on(LabeledFuture::new(low(), async {
+            let child = LabeledFuture::<TwoLevel, _>::fork(async {
+                current::taint(&TwoLevel::High).unwrap();
+            }).unwrap();
+            current::lower_clearance(TwoLevel::Low).unwrap();
+            child.await
+        })).unwrap();
+        assert_eq!(result.value().as_ref().err(), Some(&Error::ClearanceViolation));
+    }
+
+    #[test]
+    fn fork_without_context() {
+        assert!(LabeledFuture::<TwoLevel, _>::fork(async {}).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use std::panic;
+use std::thread;
+
+use super::{current, Context};
+use crate::Label;
+use crate::error::Result;
+use crate::labeled::Labeled;
+
+/// Spawn a thread running `f` with a copy of the current context.
+///
+/// The child starts with the parent's current label and clearance, and its result is labeled
+/// with the child's current label when `f` returns. Fails with
+/// [`Error::NoContext`](crate::Error::NoContext) if the calling thread has no current context
+/// for `L`.
+pub fn spawn_labeled<L, F, T>(f: F) -> Result<LabeledJoinHandle<L, T>>
+where
+    L: Label + Clone + Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let ctx: Context<L> = current::get_context()?;
+    let clearance = ctx.clearance().clone();
+    let handle = thread::spawn(move || {
+        let (result, ctx) = current::enter(ctx, f).expect("new thread has no current context");
+        Labeled::new(ctx.label().clone(), result)
+    });
+    Ok(LabeledJoinHandle { handle, clearance })
+}
+
+/// A handle to a thread started with [`spawn_labeled`].
+pub struct LabeledJoinHandle<L, T> {
+    handle: thread::JoinHandle<Labeled<L, T>>,
+    clearance: L,
+}
+
+impl<L: Label + Clone + 'static, T> LabeledJoinHandle<L, T> {
+    /// Wait for the thread to finish and return its result, tainting the current context with
+    /// the child's final label.
+    ///
+    /// If the child panicked its final label is unknown, so the current context is tainted with
+    /// the clearance the child started with before the panic is propagated.
+    pub fn join(self) -> Result<T> {
+        match self.handle.join() {
+            Ok(labeled) => {
+                current::taint(labeled.label())?;
+                Ok(labeled.into_value())
+            }
+            Err(payload) => {
+                current::taint(&self.clearance)?;
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Label;
+    use crate::dclabel::DCLabel;
+    use crate::error::Error;
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn child_inherits_context() {
+        let ctx = Context::new(label("<alice, True>"), label("<alice /\\ bob, True>")).unwrap();
+        let (handle, _) = current::enter(ctx, || {
+            spawn_labeled::<DCLabel, _, _>(|| (current::get_label(), current::get_clearance()))
+        }).unwrap();
+        let ((l, c), _) = current::enter(Context::new(DCLabel::public(), DCLabel::top()).unwrap(), || {
+            handle.unwrap().join().unwrap()
+        }).unwrap();
+        assert_eq!(l, Ok(label("<alice, True>")));
+        assert_eq!(c, Ok(label("<alice /\\ bob, True>")));
+    }
+
+    #[test]
+    fn join_taints_parent() {
+        let ctx = Context::new(DCLabel::public(), label("<alice /\\ bob, True>")).unwrap();
+        let (result, ctx) = current::enter(ctx, || {
+            let handle = spawn_labeled::<DCLabel, _, _>(|| {
+                current::taint(&label("<bob, True>")).unwrap();
+                42
+            }).unwrap();
+            assert_eq!(current::get_label(), Ok(DCLabel::public()));
+            handle.join()
+        }).unwrap();
+        assert_eq!(result, Ok(42));
+        assert_eq!(ctx.label(), &label("<bob, True>"));
+        assert!(ctx.guard_write(&DCLabel::public()).is_err());
+    }
+
+    #[test]
+    fn join_respects_parent_clearance() {
+        let ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let (handle, _) = current::enter(ctx, || {
+            spawn_labeled::<DCLabel, _, _>(|| current::taint(&label("<alice, True>")).unwrap()).unwrap()
+        }).unwrap();
+        let low = Context::new(DCLabel::public(), DCLabel::public()).unwrap();
+        let (result, low) = current::enter(low, || handle.join()).unwrap();
+        assert_eq!(result, Err(Error::ClearanceViolation));
+        assert!(low.label().can_flow_to(&DCLabel::public()));
+    }
+
+    #[test]
+    fn spawn_without_context() {
+        assert!(spawn_labeled::<DCLabel, _, _>(|| ()).is_err());
+    }
+}
@@ -1,15 +1,19 @@
-use std::collections::HashSet;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem;
 
 use super::Disjunction;
 use super::disjunction::Principal;
 
 /// A disjunctions of [Principals](Principal).
-#[derive(PartialEq, Eq, Clone)]
-pub struct Conjunction(HashSet<Disjunction>);
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct Conjunction(BTreeSet<Disjunction>);
 
 impl From<Disjunction> for Conjunction {
     fn from(s: Disjunction) -> Self {
-        let mut hs = HashSet::new();
+        let mut hs = BTreeSet::new();
         hs.insert(s);
         Conjunction(hs)
     }
@@ -27,7 +31,7 @@ impl From<bool> for Conjunction {
 
 impl From<String> for Conjunction {
     fn from(s: String) -> Self {
-        let mut hs = HashSet::new();
+        let mut hs = BTreeSet::new();
         hs.insert(Disjunction::mk_false() | s);
         Conjunction(hs)
     }
@@ -35,37 +39,57 @@ impl From<String> for Conjunction {
 
 impl From<&str> for Conjunction {
     fn from(s: &str) -> Self {
-        let mut hs = HashSet::new();
+        let mut hs = BTreeSet::new();
         hs.insert(Disjunction::mk_false() | s);
         Conjunction(hs)
     }
 }
 
-impl std::fmt::Debug for Conjunction {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+impl core::fmt::Debug for Conjunction {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
         if self.0.is_empty() {
             write!(f, "True")
         } else {
-            use std::iter::FromIterator;
+            use core::iter::FromIterator;
             let v: Vec<String> = Vec::from_iter(self.0.iter().map(|x| format!("{:?}", x)));
             write!(f, "{}", v.join(" /\\ "))
         }
     }
 }
 
+impl core::fmt::Display for Conjunction {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        if self.0.is_empty() {
+            return write!(f, "True");
+        }
+        if self.0.contains(&Disjunction::mk_false()) {
+            return write!(f, "False");
+        }
+        let mut iter = self.0.iter();
+        if let Some(head) = iter.next() {
+            write!(f, "{}", head)?;
+            for d in iter {
+                write!(f, " /\\ {}", d)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Conjunction {
     pub fn mk_true() -> Self {
-        Conjunction(HashSet::new())
+        Conjunction(BTreeSet::new())
     }
 
     pub fn mk_false() -> Self {
-        Conjunction(HashSet::new()).add(Disjunction::mk_false())
+        Conjunction(BTreeSet::new()).add(Disjunction::mk_false())
     }
 
     /// Add a disjunction clause
     ///
     /// Only adds the disjunction if no other disjunction implies it.  Simplifies the conjunction
     /// be removing any existing disjunctions implied by the new disjunction.
+    #[allow(clippy::should_implement_trait)]
     pub fn add(mut self, disj: Disjunction) -> Self {
         if self.0.iter().any(|d| d.implies(&disj)) {
             return self
@@ -86,10 +110,15 @@ impl Conjunction {
         })
     }
 
+    /// The disjunction clauses of the conjunction, in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Disjunction> {
+        self.0.iter()
+    }
+
     pub fn to_lnf(&mut self) {
-        let mut newset: HashSet<Disjunction> = HashSet::new();
+        let mut newset: BTreeSet<Disjunction> = BTreeSet::new();
 
-        let mut disjs: Vec<Disjunction> = self.0.drain().collect();
+        let mut disjs: Vec<Disjunction> = mem::take(&mut self.0).into_iter().collect();
         disjs.sort_unstable();
 
         for d1 in disjs.drain(0..) {
@@ -101,22 +130,22 @@ impl Conjunction {
     }
 }
 
-impl std::ops::BitAnd for Conjunction {
+impl core::ops::BitAnd for Conjunction {
     type Output = Self;
 
-    fn bitand(mut self, mut rhs: Self) -> Self::Output {
-        for d in rhs.0.drain() {
+    fn bitand(mut self, rhs: Self) -> Self::Output {
+        for d in rhs.0 {
             self = self.add(d);
         }
         self
     }
 }
 
-impl std::ops::BitOr for Conjunction {
+impl core::ops::BitOr for Conjunction {
     type Output = Self;
 
     fn bitor(mut self, rhs: Self) -> Self::Output {
-        let mut newset = HashSet::new();
+        let mut newset = BTreeSet::new();
 
         // Empty is true, and x | true == true
         if rhs.0.is_empty() {
@@ -128,7 +157,7 @@ impl std::ops::BitOr for Conjunction {
             return self;
         }
 
-        for s in self.0.drain() {
+        for s in mem::take(&mut self.0) {
             for r in rhs.0.iter() {
                 newset.insert(&s | r);
             }
@@ -138,7 +167,7 @@ impl std::ops::BitOr for Conjunction {
     }
 }
 
-impl std::ops::BitAnd for Disjunction {
+impl core::ops::BitAnd for Disjunction {
     type Output = Conjunction;
 
     fn bitand(self, rhs: Self) -> Self::Output {
@@ -146,7 +175,7 @@ impl std::ops::BitAnd for Disjunction {
     }
 }
 
-impl<P: Into<Principal>> std::ops::BitOr<P> for Conjunction {
+impl<P: Into<Principal>> core::ops::BitOr<P> for Conjunction {
     type Output = Self;
 
     fn bitor(self, rhs: P) -> Self {
@@ -154,7 +183,7 @@ impl<P: Into<Principal>> std::ops::BitOr<P> for Conjunction {
     }
 }
 
-impl<P: Into<Principal>> std::ops::BitAnd<P> for Conjunction {
+impl<P: Into<Principal>> core::ops::BitAnd<P> for Conjunction {
     type Output = Self;
 
     fn bitand(self, rhs: P) -> Self {
@@ -179,6 +208,7 @@ impl quickcheck::Arbitrary for Conjunction {
 }
 
 #[cfg(test)]
+#[allow(non_fmt_panics)]
 mod tests {
     use super::*;
 
@@ -218,6 +248,14 @@ mod tests {
         assert_eq!(l2 | l1.clone(), l1);
     }
 
+    #[test]
+    fn display() {
+        assert_eq!(format!("{}", Conjunction::mk_true()), "True");
+        assert_eq!(format!("{}", Conjunction::mk_false()), "False");
+        let c = (Conjunction::mk_false() | "bob" | "alice") & "hr";
+        assert_eq!(format!("{}", c), "(alice \\/ bob) /\\ (hr)");
+    }
+
     quickcheck! {
         fn or_is_symmetric(c1: Conjunction, c2: Conjunction) -> bool {
             c1.clone() | c2.clone() == c2 | c1
@@ -1,5 +1,8 @@
-use std::collections::BTreeSet;
-use std::fmt;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
 
 pub type Principal = String;
 
@@ -7,20 +10,20 @@ pub type Principal = String;
 #[derive(PartialEq, Eq, Clone, PartialOrd, Ord)]
 pub struct Disjunction(BTreeSet<Principal>);
 
-impl std::fmt::Debug for Disjunction {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+impl core::fmt::Debug for Disjunction {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
         if self.0.is_empty() {
             write!(f, "False")
         } else {
-            use std::iter::FromIterator;
+            use core::iter::FromIterator;
             let v: Vec<String> = Vec::from_iter(self.0.iter().map(|x| format!("{:?}", x)));
             write!(f, "{}", v.join(" \\/ "))
         }
     }
 }
 
-impl std::hash::Hash for Disjunction {
-    fn hash<H: std::hash::Hasher>(&self, hasher: &mut H) {
+impl core::hash::Hash for Disjunction {
+    fn hash<H: core::hash::Hasher>(&self, hasher: &mut H) {
         for p in self.0.iter() {
             p.hash(hasher);
         }
@@ -33,11 +36,17 @@ impl Disjunction {
     }
 
     /// Add a principal to the disjunction
+    #[allow(clippy::should_implement_trait)]
     pub fn add<P: Into<Principal>>(mut self, principal: P) -> Self {
         self.0.insert(principal.into());
         self
     }
 
+    /// The principals of the disjunction, in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Principal> {
+        self.0.iter()
+    }
+
     /// The disjunction implies another disjunction
     ///
     /// Returns true if the disjunction contains a subset of the principals present in rhs, or if
@@ -64,7 +73,7 @@ impl<I: Into<String>> From<I> for Disjunction {
     }
 }
 
-impl std::ops::BitOr for &Disjunction {
+impl core::ops::BitOr for &Disjunction {
     type Output = Disjunction;
 
     fn bitor(self, rhs: Self) -> Disjunction {
@@ -72,7 +81,7 @@ impl std::ops::BitOr for &Disjunction {
     }
 }
 
-impl<P: Into<Principal>> std::ops::BitOr<P> for Disjunction {
+impl<P: Into<Principal>> core::ops::BitOr<P> for Disjunction {
     type Output = Self;
 
     fn bitor(self, rhs: P) -> Self {
@@ -85,21 +94,35 @@ impl fmt::Display for Disjunction {
         write!(f, "(")?;
         let mut iter = self.0.iter();
         if let Some(head) = iter.next() {
-            head.fmt(f)?;
+            fmt_principal(head, f)?;
             for p in iter {
-                write!(f, " \\/ {}", p)?;
+                write!(f, " \\/ ")?;
+                fmt_principal(p, f)?;
             }
         }
         write!(f, ")")
     }
 }
 
+/// Write a principal so that it can be read back by the label parser.
+///
+/// Principals made only of identifier characters are written as-is, anything else (including
+/// the keywords `True` and `False`) is quoted.
+fn fmt_principal(p: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if super::parse::is_bare_principal(p) {
+        write!(f, "{}", p)
+    } else {
+        write!(f, "{:?}", p)
+    }
+}
+
 #[cfg(test)]
 impl quickcheck::Arbitrary for Disjunction {
     fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Self {
         Disjunction(quickcheck::Arbitrary::arbitrary(g))
     }
 
+    #[allow(clippy::redundant_closure)]
     fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
         let Disjunction(ref x) = self;
         let xs = x.shrink();
@@ -140,6 +163,13 @@ mod test {
         assert_eq!(format!("{}", d), "(bar \\/ baz \\/ foo)");
     }
 
+    #[test]
+    fn display_quoted_principals() {
+        let d = Disjunction::mk_false() | "True" | "alice smith";
+
+        assert_eq!(format!("{}", d), "(\"True\" \\/ \"alice smith\")");
+    }
+
     #[test]
     fn false_implies_false() {
         let d0 = Disjunction::mk_false();
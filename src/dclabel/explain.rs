@@ -0,0 +1,124 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{DCLabel, Disjunction};
+
+/// The outcome of [`DCLabel::explain_flow`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FlowVerdict {
+    Allowed,
+    /// The flow is denied. Each list is sorted, and at least one of them is non-empty.
+    Denied {
+        /// Secrecy clauses of the source that the target's secrecy does not imply. The target
+        /// would let someone read the data without speaking for these.
+        secrecy: Vec<Disjunction>,
+        /// Integrity clauses of the target that the source's integrity does not imply. The
+        /// source is not vouched for by these, as the target requires.
+        integrity: Vec<Disjunction>,
+    },
+}
+
+impl FlowVerdict {
+    pub fn is_allowed(&self) -> bool {
+        *self == FlowVerdict::Allowed
+    }
+}
+
+impl fmt::Display for FlowVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlowVerdict::Allowed => write!(f, "flow allowed"),
+            FlowVerdict::Denied { secrecy, integrity } => {
+                write!(f, "flow denied:")?;
+                for d in secrecy {
+                    write!(f, "\n  secrecy clause {} of the source", d)?;
+                    write!(f, " is not implied by the target's secrecy")?;
+                }
+                for d in integrity {
+                    write!(f, "\n  integrity clause {} of the target", d)?;
+                    write!(f, " is not implied by the source's integrity")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl DCLabel {
+    /// Like [`can_flow_to`](crate::Label::can_flow_to), but on denial reports which clauses
+    /// are responsible.
+    pub fn explain_flow(&self, rhs: &Self) -> FlowVerdict {
+        let unmet = |have: &super::Conjunction, need: &super::Conjunction| {
+            let mut unmet: Vec<Disjunction> = need.iter()
+                .filter(|d| !have.implies(&(*d).clone().into()))
+                .cloned()
+                .collect();
+            unmet.sort();
+            unmet
+        };
+        let secrecy = unmet(&rhs.secrecy, &self.secrecy);
+        let integrity = unmet(&self.integrity, &rhs.integrity);
+        if secrecy.is_empty() && integrity.is_empty() {
+            FlowVerdict::Allowed
+        } else {
+            FlowVerdict::Denied { secrecy, integrity }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Label;
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    fn clauses(s: &[&str]) -> Vec<Disjunction> {
+        s.iter().map(|d| d.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn allowed() {
+        assert_eq!(DCLabel::public().explain_flow(&label("<alice, True>")), FlowVerdict::Allowed);
+    }
+
+    #[test]
+    fn secrecy_denied() {
+        let from = label("<alice /\\ (bob \\/ carol) /\\ dave, True>");
+        let verdict = from.explain_flow(&label("<dave, True>"));
+        let secrecy = clauses(&["alice", "bob \\/ carol"]);
+        assert_eq!(verdict, FlowVerdict::Denied { secrecy, integrity: vec![] });
+    }
+
+    #[test]
+    fn integrity_denied() {
+        let verdict = label("<True, alice>").explain_flow(&label("<True, alice /\\ bob>"));
+        assert_eq!(verdict, FlowVerdict::Denied { secrecy: vec![], integrity: clauses(&["bob"]) });
+        assert_eq!(
+            verdict.to_string(),
+            "flow denied:\n  integrity clause (bob) of the target is not implied by the source's integrity"
+        );
+    }
+
+    #[test]
+    fn both_denied() {
+        match DCLabel::top().explain_flow(&DCLabel::bottom()) {
+            FlowVerdict::Denied { secrecy, integrity } => {
+                assert_eq!(secrecy, clauses(&["()"]));
+                assert_eq!(integrity, clauses(&["()"]));
+            }
+            v => panic!("unexpected verdict {:?}", v),
+        }
+    }
+
+    fn agrees_with_can_flow_to(l1: DCLabel, l2: DCLabel) -> bool {
+        l1.explain_flow(&l2).is_allowed() == l1.can_flow_to(&l2)
+    }
+
+    #[test]
+    fn explain_agrees_with_can_flow_to() {
+        crate::qc_tests::small().quickcheck(agrees_with_can_flow_to as fn(DCLabel, DCLabel) -> bool);
+    }
+}
@@ -1,10 +1,19 @@
+use crate::Label;
+
 mod disjunction;
 mod conjunction;
+mod explain;
+mod parse;
+mod privilege;
 
-pub use disjunction::Disjunction;
+pub use disjunction::{Disjunction, Principal};
 pub use conjunction::Conjunction;
+pub use explain::FlowVerdict;
+pub use parse::ParseError;
+pub(crate) use parse::is_bare_principal;
+pub use privilege::{required_privilege, Privilege};
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct DCLabel {
     secrecy: Conjunction,
     integrity: Conjunction,
@@ -26,9 +35,48 @@ impl DCLabel {
     pub fn bottom() -> Self {
         DCLabel::new(true, false)
     }
+
+    pub fn secrecy(&self) -> &Conjunction {
+        &self.secrecy
+    }
+
+    pub fn integrity(&self) -> &Conjunction {
+        &self.integrity
+    }
+
+    /// Can-flow-to relation when exercising `privilege` (⊑ₚ).
+    ///
+    /// The privilege may be used to bypass the secrecy of `self` and to endorse the integrity
+    /// of `rhs`. With the empty privilege this is the same as [`can_flow_to`](Label::can_flow_to).
+    pub fn can_flow_to_with(&self, rhs: &Self, privilege: &Privilege) -> bool {
+        let p = privilege.description();
+        (rhs.secrecy.clone() & p.clone()).implies(&self.secrecy)
+            && (self.integrity.clone() & p.clone()).implies(&rhs.integrity)
+    }
+
+    /// The lowest label that `self` can flow to when exercising `privilege`.
+    ///
+    /// Secrecy clauses implied by the privilege are removed and the privilege is added to the
+    /// integrity.
+    pub fn downgrade(&self, privilege: &Privilege) -> Self {
+        let p = privilege.description();
+        let mut secrecy = Conjunction::mk_true();
+        for d in self.secrecy.iter() {
+            if !p.implies(&d.clone().into()) {
+                secrecy = secrecy.add(d.clone());
+            }
+        }
+        DCLabel::new(secrecy, self.integrity.clone() & p.clone())
+    }
+}
+
+impl core::fmt::Display for DCLabel {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        write!(f, "<{}, {}>", self.secrecy, self.integrity)
+    }
 }
 
-impl super::Label for DCLabel {
+impl Label for DCLabel {
 
     fn join(&self, rhs: &Self) -> Self {
         let secrecy = {
@@ -84,6 +132,7 @@ impl quickcheck::Arbitrary for DCLabel {
 }
 
 #[cfg(test)]
+#[allow(non_fmt_panics)]
 mod tests {
     use super::*;
     use crate::Label;
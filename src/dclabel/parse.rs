@@ -0,0 +1,302 @@
+//! Text syntax for DC labels.
+//!
+//! A label is written `<secrecy, integrity>`, where each component is a formula in conjunctive
+//! normal form: `True`, `False`, or clauses separated by `/\`. A clause is either a
+//! parenthesized disjunction of principals, `(alice \/ bob)`, or a bare disjunction,
+//! `alice \/ bob`. Principals are identifiers or double-quoted strings using Rust escapes.
+//!
+//! ```
+//! use label::dclabel::DCLabel;
+//!
+//! let l: DCLabel = r"<alice /\ (hr \/ legal), True>".parse().unwrap();
+//! assert_eq!(l.to_string(), r"<(alice) /\ (hr \/ legal), True>");
+//! ```
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::str::FromStr;
+
+use super::{Conjunction, DCLabel, Disjunction};
+
+/// An error encountered while parsing a label.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    position: usize,
+    message: String,
+}
+
+impl ParseError {
+    fn new<M: Into<String>>(position: usize, message: M) -> Self {
+        ParseError { position, message: message.into() }
+    }
+
+    /// Byte offset in the input at which the error was detected.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+fn is_principal_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-.@:".contains(c)
+}
+
+pub(crate) fn is_bare_principal(p: &str) -> bool {
+    !p.is_empty() && p != "True" && p != "False" && p.chars().all(is_principal_char)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser { input, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", token)))
+        }
+    }
+
+    fn error<M: Into<String>>(&self, message: M) -> ParseError {
+        ParseError::new(self.pos, message)
+    }
+
+    fn finish(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.rest().is_empty() {
+            Ok(())
+        } else {
+            Err(self.error("unexpected trailing input"))
+        }
+    }
+
+    fn label(&mut self) -> Result<DCLabel, ParseError> {
+        self.expect("<")?;
+        let secrecy = self.conjunction()?;
+        self.expect(",")?;
+        let integrity = self.conjunction()?;
+        self.expect(">")?;
+        Ok(DCLabel::new(secrecy, integrity))
+    }
+
+    fn conjunction(&mut self) -> Result<Conjunction, ParseError> {
+        let mut conj = Conjunction::mk_true();
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            match self.word() {
+                Some("True") => {}
+                Some("False") => conj = conj.add(Disjunction::mk_false()),
+                _ => {
+                    self.pos = start;
+                    conj = conj.add(self.clause()?);
+                }
+            }
+            if !self.eat("/\\") {
+                return Ok(conj);
+            }
+        }
+    }
+
+    fn clause(&mut self) -> Result<Disjunction, ParseError> {
+        if self.eat("(") {
+            if self.eat(")") {
+                return Ok(Disjunction::mk_false());
+            }
+            let disj = self.disjunction()?;
+            self.expect(")")?;
+            Ok(disj)
+        } else {
+            self.disjunction()
+        }
+    }
+
+    fn disjunction(&mut self) -> Result<Disjunction, ParseError> {
+        let mut disj = Disjunction::mk_false() | self.principal()?;
+        while self.eat("\\/") {
+            disj = disj | self.principal()?;
+        }
+        Ok(disj)
+    }
+
+    fn word(&mut self) -> Option<&'a str> {
+        let rest = self.rest();
+        let len = rest.find(|c| !is_principal_char(c)).unwrap_or(rest.len());
+        if len == 0 {
+            None
+        } else {
+            self.pos += len;
+            Some(&rest[..len])
+        }
+    }
+
+    fn principal(&mut self) -> Result<String, ParseError> {
+        self.skip_whitespace();
+        if self.rest().starts_with('"') {
+            return self.quoted();
+        }
+        let start = self.pos;
+        match self.word() {
+            Some(w) if is_bare_principal(w) => Ok(w.to_string()),
+            Some(w) => {
+                self.pos = start;
+                Err(self.error(format!("`{}` is a keyword, quote it to use it as a principal", w)))
+            }
+            None => Err(self.error("expected a principal")),
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 't')) => '\t',
+                        Some((_, '0')) => '\0',
+                        Some((_, '\\')) => '\\',
+                        Some((_, '"')) => '"',
+                        Some((_, '\'')) => '\'',
+                        Some((j, 'u')) => {
+                            let hex = &self.rest()[j + 1..];
+                            let end = hex.find('}').filter(|_| hex.starts_with('{'));
+                            let c = end
+                                .and_then(|end| u32::from_str_radix(&hex[1..end], 16).ok())
+                                .and_then(core::char::from_u32)
+                                .ok_or_else(|| ParseError::new(start + j, "invalid unicode escape"))?;
+                            for _ in 0..end.unwrap_or(0) + 1 {
+                                chars.next();
+                            }
+                            c
+                        }
+                        Some((j, _)) => return Err(ParseError::new(start + j, "invalid escape")),
+                        None => break,
+                    };
+                    out.push(escaped);
+                }
+                c => out.push(c),
+            }
+        }
+        Err(ParseError::new(start, "unterminated string"))
+    }
+}
+
+impl FromStr for DCLabel {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let label = parser.label()?;
+        parser.finish()?;
+        Ok(label)
+    }
+}
+
+impl FromStr for Conjunction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let conj = parser.conjunction()?;
+        parser.finish()?;
+        Ok(conj)
+    }
+}
+
+impl FromStr for Disjunction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let disj = parser.clause()?;
+        parser.finish()?;
+        Ok(disj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Label;
+
+    #[test]
+    fn parse_constants() {
+        assert_eq!("<True, True>".parse(), Ok(DCLabel::public()));
+        assert_eq!("<False, True>".parse(), Ok(DCLabel::top()));
+        assert_eq!("<True, False>".parse(), Ok(DCLabel::bottom()));
+    }
+
+    #[test]
+    fn parse_clauses() {
+        let l: DCLabel = "< alice /\\ (hr \\/ legal) , bob \\/ carol >".parse().unwrap();
+        let secrecy = (Conjunction::mk_false() | "hr" | "legal") & "alice";
+        let integrity = Conjunction::mk_false() | "bob" | "carol";
+        assert_eq!(l, DCLabel::new(secrecy, integrity));
+    }
+
+    #[test]
+    fn parse_quoted() {
+        let c: Conjunction = "\"True\" /\\ \"a \\\"b\\\"\\u{e9}\"".parse().unwrap();
+        assert_eq!(c, Conjunction::mk_true() & "True" & "a \"b\"\u{e9}");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("<alice>".parse::<DCLabel>().unwrap_err().position(), 6);
+        assert_eq!("<alice, True> x".parse::<DCLabel>().unwrap_err().position(), 14);
+        assert_eq!("(alice \\/ True)".parse::<Disjunction>().unwrap_err().position(), 10);
+        assert_eq!("\"alice".parse::<Conjunction>().unwrap_err().position(), 0);
+    }
+
+    fn display_round_trips(l: DCLabel) -> bool {
+        let parsed: DCLabel = l.to_string().parse().unwrap();
+        parsed.can_flow_to(&l) && l.can_flow_to(&parsed)
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        crate::qc_tests::small().quickcheck(display_round_trips as fn(DCLabel) -> bool);
+    }
+}
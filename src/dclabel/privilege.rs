@@ -0,0 +1,148 @@
+use core::fmt;
+
+use super::{Conjunction, DCLabel, FlowVerdict};
+
+/// The authority to bypass the restrictions of a set of principals.
+///
+/// A privilege is described by a [`Conjunction`]: owning `alice /\ (bob \/ carol)` means
+/// speaking for `alice`, and for at least one of `bob` or `carol`. The privilege `True` is the
+/// empty privilege, which bypasses nothing.
+///
+/// Privileges cannot be made up from a description: outside this crate they are only obtained
+/// from a [`Policy`](crate::policy::Policy).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Privilege(Conjunction);
+
+impl Privilege {
+    pub(crate) fn new<C: Into<Conjunction>>(description: C) -> Self {
+        Privilege(description.into())
+    }
+
+    /// The empty privilege.
+    pub fn none() -> Self {
+        Privilege(Conjunction::mk_true())
+    }
+
+    pub fn description(&self) -> &Conjunction {
+        &self.0
+    }
+
+    /// This privilege is at least as powerful as `rhs`.
+    pub fn implies(&self, rhs: &Self) -> bool {
+        self.0.implies(&rhs.0)
+    }
+}
+
+/// The description of the weakest privilege with which `from` can flow to `to`.
+///
+/// Every clause reported by [`DCLabel::explain_flow`] must be implied by the privilege, so the
+/// result is their conjunction: any privilege `p` such that `from.can_flow_to_with(to, &p)`
+/// satisfies `p.description().implies(&required_privilege(from, to))`. If `from` can already
+/// flow to `to` this is `True`, the description of the empty privilege.
+pub fn required_privilege(from: &DCLabel, to: &DCLabel) -> Conjunction {
+    match from.explain_flow(to) {
+        FlowVerdict::Allowed => Conjunction::mk_true(),
+        FlowVerdict::Denied { secrecy, integrity } => {
+            let mut p = Conjunction::mk_true();
+            for d in secrecy.into_iter().chain(integrity) {
+                p = p.add(d);
+            }
+            p
+        }
+    }
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Label;
+    use crate::dclabel::DCLabel;
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    fn privilege(s: &str) -> Privilege {
+        Privilege::new(s.parse::<Conjunction>().unwrap())
+    }
+
+    #[test]
+    fn privilege_bypasses_secrecy() {
+        let secret = label("<alice /\\ bob, True>");
+        assert!(!secret.can_flow_to(&DCLabel::public()));
+        assert!(!secret.can_flow_to_with(&DCLabel::public(), &privilege("alice")));
+        assert!(secret.can_flow_to_with(&DCLabel::public(), &privilege("alice /\\ bob")));
+        assert!(secret.can_flow_to_with(&label("<bob, True>"), &privilege("alice")));
+    }
+
+    #[test]
+    fn privilege_endorses_integrity() {
+        let endorsed = label("<True, alice>");
+        assert!(!DCLabel::public().can_flow_to(&endorsed));
+        assert!(DCLabel::public().can_flow_to_with(&endorsed, &privilege("alice")));
+    }
+
+    #[test]
+    fn no_privilege_is_can_flow_to() {
+        let l1 = label("<alice, bob>");
+        let l2 = label("<alice /\\ carol, True>");
+        assert_eq!(l1.can_flow_to_with(&l2, &Privilege::none()), l1.can_flow_to(&l2));
+        assert_eq!(l2.can_flow_to_with(&l1, &Privilege::none()), l2.can_flow_to(&l1));
+    }
+
+    #[test]
+    fn downgrade() {
+        let l = label("<alice /\\ (bob \\/ carol), True>");
+        assert_eq!(l.downgrade(&privilege("bob")), label("<alice, bob>"));
+        assert_eq!(l.downgrade(&privilege("alice /\\ carol")), label("<True, alice /\\ carol>"));
+        assert_eq!(l.downgrade(&Privilege::none()), l);
+    }
+
+    #[test]
+    fn required_privilege_for_secrecy_and_integrity() {
+        let from = label("<alice /\\ (bob \\/ carol), dave>");
+        let to = label("<bob, dave /\\ erin>");
+        let p = Privilege::new(required_privilege(&from, &to));
+        assert_eq!(p, privilege("alice /\\ erin"));
+        assert!(from.can_flow_to_with(&to, &p));
+        assert!(!from.can_flow_to_with(&to, &privilege("alice")));
+        assert!(!from.can_flow_to_with(&to, &privilege("erin")));
+    }
+
+    #[test]
+    fn required_privilege_when_allowed() {
+        assert_eq!(required_privilege(&DCLabel::public(), &DCLabel::top()), Conjunction::mk_true());
+    }
+
+    fn required_privilege_is_sufficient(from: DCLabel, to: DCLabel) -> bool {
+        from.can_flow_to_with(&to, &Privilege::new(required_privilege(&from, &to)))
+    }
+
+    fn required_privilege_is_minimal(from: DCLabel, to: DCLabel, p: Conjunction) -> bool {
+        !from.can_flow_to_with(&to, &Privilege::new(p.clone())) || p.implies(&required_privilege(&from, &to))
+    }
+
+    fn downgrade_flows_with_privilege(l: DCLabel, p: Conjunction) -> bool {
+        let p = Privilege::new(p);
+        let lowered = l.downgrade(&p);
+        l.can_flow_to_with(&lowered, &p) && lowered.can_flow_to(&l.join(&lowered))
+    }
+
+    #[test]
+    fn required_privilege_properties() {
+        let qc = || crate::qc_tests::small();
+        qc().quickcheck(required_privilege_is_sufficient as fn(DCLabel, DCLabel) -> bool);
+        qc().quickcheck(required_privilege_is_minimal as fn(DCLabel, DCLabel, Conjunction) -> bool);
+    }
+
+    #[test]
+    fn downgrade_properties() {
+        crate::qc_tests::small().quickcheck(downgrade_flows_with_privilege as fn(DCLabel, Conjunction) -> bool);
+    }
+}
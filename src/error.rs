@@ -0,0 +1,41 @@
+use core::fmt;
+
+/// Errors raised when an operation would violate the information flow policy.
+///
+/// Which variants exist depends on the enabled features, so the enum is non-exhaustive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The current label cannot flow to the label of the object being written.
+    FlowViolation,
+    /// The operation would raise the current label above the clearance.
+    ClearanceViolation,
+    /// The other end of a labeled channel has been dropped.
+    Disconnected,
+    /// No current context is installed for this label type.
+    NoContext,
+    /// The caller's privilege does not meet the threshold of a gate.
+    InsufficientPrivilege,
+    /// A privileged operation was refused because it could not be audited.
+    #[cfg(feature = "std")]
+    AuditFailed(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::FlowViolation => write!(f, "current label cannot flow to the target label"),
+            Error::ClearanceViolation => write!(f, "label would exceed the current clearance"),
+            Error::Disconnected => write!(f, "channel is disconnected"),
+            Error::NoContext => write!(f, "no current label context"),
+            Error::InsufficientPrivilege => write!(f, "privilege does not meet the gate's threshold"),
+            #[cfg(feature = "std")]
+            Error::AuditFailed(e) => write!(f, "failed to record audit event: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+pub type Result<T> = core::result::Result<T, Error>;
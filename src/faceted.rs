@@ -0,0 +1,259 @@
+//! Faceted values, after Austin and Flanagan's _Multiple Facets for Dynamic Information Flow_.
+//!
+//! A faceted value `⟨k ? private : public⟩` shows its private facet to observers whose clearance
+//! the label `k` can flow to, and its public facet to everyone else. Facets nest, so a single
+//! [`Faceted`] value holds the view of every observer, and computing on it computes every view
+//! at once. The same data can then be served to users of different clearances without
+//! branching on who is asking.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::Label;
+use crate::context::Context;
+use crate::error::Result;
+use crate::labeled::Labeled;
+
+enum Node<L, T> {
+    Raw(T),
+    Split { label: L, private: Box<Node<L, T>>, public: Box<Node<L, T>> },
+}
+
+impl<L: Label + Clone, T> Node<L, T> {
+    /// Drop the facets that no observer on the given side of `label` can see.
+    ///
+    /// An observer who sees the private facet of `label` also sees that of every label flowing
+    /// to it, and one who sees the public facet of `label` also sees that of every label it flows
+    /// to.
+    fn restrict(self, label: &L, private: bool) -> Self {
+        match self {
+            Node::Raw(value) => Node::Raw(value),
+            Node::Split { label: l, private: p, public: q } => {
+                if private && l.can_flow_to(label) {
+                    p.restrict(label, private)
+                } else if !private && label.can_flow_to(&l) {
+                    q.restrict(label, private)
+                } else {
+                    Node::Split {
+                        label: l,
+                        private: Box::new(p.restrict(label, private)),
+                        public: Box::new(q.restrict(label, private)),
+                    }
+                }
+            }
+        }
+    }
+
+    fn bind<U>(self, f: &mut dyn FnMut(T) -> Node<L, U>, path: &mut Vec<(L, bool)>) -> Node<L, U> {
+        match self {
+            Node::Raw(value) => {
+                path.iter().fold(f(value), |node, (label, private)| node.restrict(label, *private))
+            }
+            Node::Split { label, private, public } => {
+                path.push((label.clone(), true));
+                let private = private.bind(f, path);
+                path.last_mut().expect("pushed above").1 = false;
+                let public = public.bind(f, path);
+                path.pop();
+                Node::Split { label, private: Box::new(private), public: Box::new(public) }
+            }
+        }
+    }
+
+    /// The facet seen at `clearance`, and the join of the labels of the private facets taken.
+    fn view(&self, clearance: &L) -> (&T, Option<L>) {
+        let mut node = self;
+        let mut seen: Option<L> = None;
+        loop {
+            match node {
+                Node::Raw(value) => return (value, seen),
+                Node::Split { label, private, public } => {
+                    if label.can_flow_to(clearance) {
+                        seen = Some(match seen {
+                            Some(seen) => seen.join(label),
+                            None => label.clone(),
+                        });
+                        node = private;
+                    } else {
+                        node = public;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<L: fmt::Debug, T> fmt::Debug for Node<L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Node::Raw(_) => f.write_str("<hidden>"),
+            Node::Split { label, private, public } => write!(f, "⟨{:?} ? {:?} : {:?}⟩", label, private, public),
+        }
+    }
+}
+
+/// A value with a private and a public facet, guarded by a label. See the
+/// [module documentation](self).
+pub struct Faceted<L, T> {
+    node: Node<L, T>,
+}
+
+impl<L: Label + Clone, T> Faceted<L, T> {
+    /// A value showing `private` to observers whose clearance `label` can flow to, and `public`
+    /// to everyone else.
+    pub fn new(label: L, private: T, public: T) -> Self {
+        Faceted::split(label, Faceted::from(private), Faceted::from(public))
+    }
+
+    /// Like [`Faceted::new`], with faceted facets.
+    pub fn split(label: L, private: Faceted<L, T>, public: Faceted<L, T>) -> Self {
+        let private = private.node.restrict(&label, true);
+        let public = public.node.restrict(&label, false);
+        Faceted { node: Node::Split { label, private: Box::new(private), public: Box::new(public) } }
+    }
+
+    /// A value whose private facet is `labeled`, and whose public facet is `public`.
+    pub fn from_labeled(labeled: Labeled<L, T>, public: T) -> Self {
+        let (label, private) = labeled.into_parts();
+        Faceted::new(label, private, public)
+    }
+
+    /// The facet an observer with clearance `clearance` sees.
+    pub fn observe(&self, clearance: &L) -> &T {
+        self.node.view(clearance).0
+    }
+
+    /// Observe the value at the clearance of `ctx`, tainting it with the labels of the private
+    /// facets observed.
+    pub fn read(&self, ctx: &mut Context<L>) -> Result<T>
+    where
+        T: Clone,
+    {
+        let (value, seen) = self.node.view(ctx.clearance());
+        if let Some(label) = seen {
+            ctx.taint(&label)?;
+        }
+        Ok(value.clone())
+    }
+
+    /// Apply `f`, which produces a faceted value, to every facet.
+    ///
+    /// Facets of the result that no observer of the facet `f` was applied to can see are
+    /// dropped.
+    pub fn and_then<U, F: FnMut(T) -> Faceted<L, U>>(self, mut f: F) -> Faceted<L, U> {
+        Faceted { node: self.node.bind(&mut |value| f(value).node, &mut Vec::new()) }
+    }
+
+    /// Apply `f` to every facet.
+    pub fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> Faceted<L, U> {
+        self.and_then(|value| Faceted::from(f(value)))
+    }
+
+    /// Combine the facets of two faceted values with `f`, so that every observer sees `f`
+    /// applied to what they see of each.
+    pub fn zip_with<U, R, F>(self, other: Faceted<L, U>, mut f: F) -> Faceted<L, R>
+    where
+        T: Clone,
+        U: Clone,
+        F: FnMut(T, U) -> R,
+    {
+        self.and_then(|a| other.clone().map(|b| f(a.clone(), b)))
+    }
+}
+
+impl<L, T> From<T> for Faceted<L, T> {
+    /// A value that every observer sees the same.
+    fn from(value: T) -> Self {
+        Faceted { node: Node::Raw(value) }
+    }
+}
+
+impl<L: Clone, T: Clone> Clone for Node<L, T> {
+    fn clone(&self) -> Self {
+        match self {
+            Node::Raw(value) => Node::Raw(value.clone()),
+            Node::Split { label, private, public } => {
+                Node::Split { label: label.clone(), private: private.clone(), public: public.clone() }
+            }
+        }
+    }
+}
+
+impl<L: Clone, T: Clone> Clone for Faceted<L, T> {
+    fn clone(&self) -> Self {
+        Faceted { node: self.node.clone() }
+    }
+}
+
+impl<L: fmt::Debug, T> fmt::Debug for Faceted<L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Faceted({:?})", self.node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dclabel::DCLabel;
+    use crate::twolevel::TwoLevel;
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn observe_by_clearance() {
+        let location = Faceted::new(TwoLevel::High, "Lisbon", "Europe");
+        assert_eq!(location.observe(&TwoLevel::High), &"Lisbon");
+        assert_eq!(location.observe(&TwoLevel::Low), &"Europe");
+    }
+
+    #[test]
+    fn zip_with_computes_every_view() {
+        let alice = label("<alice, True>");
+        let bob = label("<bob, True>");
+        let a = Faceted::new(alice.clone(), 100, 0);
+        let b = Faceted::new(bob.clone(), 20, 0);
+        let total = a.zip_with(b, |a, b| a + b);
+
+        assert_eq!(total.observe(&DCLabel::public()), &0);
+        assert_eq!(total.observe(&alice), &100);
+        assert_eq!(total.observe(&bob), &20);
+        assert_eq!(total.observe(&alice.join(&bob)), &120);
+    }
+
+    #[test]
+    fn same_label_facets_do_not_mix() {
+        let salary = Faceted::new(TwoLevel::High, 90, 0);
+        let doubled = salary.clone().zip_with(salary, |a, b| a + b);
+        assert_eq!(format!("{:?}", doubled), "Faceted(⟨High ? <hidden> : <hidden>⟩)");
+        assert_eq!(doubled.observe(&TwoLevel::High), &180);
+        assert_eq!(doubled.observe(&TwoLevel::Low), &0);
+    }
+
+    #[test]
+    fn and_then_and_map() {
+        let user = Faceted::new(TwoLevel::High, "admin", "guest");
+        let greeting = user
+            .and_then(|name| match name {
+                "admin" => Faceted::new(TwoLevel::High, 2, 1),
+                _ => Faceted::from(0),
+            })
+            .map(|n| n * 10);
+        assert_eq!(greeting.observe(&TwoLevel::High), &20);
+        assert_eq!(greeting.observe(&TwoLevel::Low), &0);
+    }
+
+    #[test]
+    fn read_taints_by_facets_seen() {
+        let doc = Faceted::from_labeled(Labeled::new(label("<alice, True>"), "draft"), "");
+        let mut public = Context::new(DCLabel::public(), DCLabel::public()).unwrap();
+        assert_eq!(doc.read(&mut public), Ok(""));
+        assert_eq!(public.label(), &DCLabel::public());
+
+        let mut reader = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        assert_eq!(doc.read(&mut reader), Ok("draft"));
+        assert_eq!(reader.label(), &label("<alice, True>"));
+    }
+}
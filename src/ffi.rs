@@ -0,0 +1,215 @@
+//! C interface to [`DCLabel`]s.
+//!
+//! Labels cross the boundary as opaque `DCLabel *` handles, owned by the caller and released
+//! with `label_dclabel_free`. Every fallible function returns a [`LabelStatus`] and writes its
+//! result through an out-pointer, which is left untouched unless the status is
+//! `LABEL_STATUS_OK`. Panics are caught and reported as `LABEL_STATUS_PANIC`; they never unwind
+//! into C.
+//!
+//! The `label-capi` crate in `capi/` builds this module as static and shared libraries. Its
+//! `include/label.h` holds the C declarations, generated from this module by cbindgen. The module
+//! is only compiled with the `ffi` feature, which that crate enables, so that other dependents do
+//! not export its symbols.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, UnwindSafe};
+
+use crate::Label;
+use crate::dclabel::DCLabel;
+
+/// The result of a call through the C interface.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabelStatus {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// A string argument was not a valid label.
+    ParseError = 3,
+    /// The library panicked. This is a bug.
+    Panic = 4,
+}
+
+fn guard<F: FnOnce() -> Result<(), LabelStatus> + UnwindSafe>(f: F) -> LabelStatus {
+    match panic::catch_unwind(f) {
+        Ok(Ok(())) => LabelStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => LabelStatus::Panic,
+    }
+}
+
+unsafe fn deref<'a, T>(p: *const T) -> Result<&'a T, LabelStatus> {
+    p.as_ref().ok_or(LabelStatus::NullPointer)
+}
+
+unsafe fn write_out<T>(out: *mut T, value: T) -> Result<(), LabelStatus> {
+    if out.is_null() {
+        return Err(LabelStatus::NullPointer);
+    }
+    out.write(value);
+    Ok(())
+}
+
+/// Parse the NUL-terminated string `text` into a new label, stored in `*out`.
+///
+/// # Safety
+///
+/// `text` must be null or a valid NUL-terminated string, and `out` must be null or valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn label_dclabel_parse(text: *const c_char, out: *mut *mut DCLabel) -> LabelStatus {
+    guard(|| {
+        if text.is_null() || out.is_null() {
+            return Err(LabelStatus::NullPointer);
+        }
+        let text = CStr::from_ptr(text).to_str().map_err(|_| LabelStatus::InvalidUtf8)?;
+        let label: DCLabel = text.parse().map_err(|_| LabelStatus::ParseError)?;
+        write_out(out, Box::into_raw(Box::new(label)))
+    })
+}
+
+/// Store the join of `lhs` and `rhs` in `*out`, as a new label.
+///
+/// # Safety
+///
+/// `lhs` and `rhs` must be null or live handles, and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn label_dclabel_join(
+    lhs: *const DCLabel, rhs: *const DCLabel, out: *mut *mut DCLabel,
+) -> LabelStatus {
+    guard(|| {
+        let joined = deref(lhs)?.join(deref(rhs)?);
+        write_out(out, Box::into_raw(Box::new(joined)))
+    })
+}
+
+/// Store the meet of `lhs` and `rhs` in `*out`, as a new label.
+///
+/// # Safety
+///
+/// `lhs` and `rhs` must be null or live handles, and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn label_dclabel_meet(
+    lhs: *const DCLabel, rhs: *const DCLabel, out: *mut *mut DCLabel,
+) -> LabelStatus {
+    guard(|| {
+        let met = deref(lhs)?.meet(deref(rhs)?);
+        write_out(out, Box::into_raw(Box::new(met)))
+    })
+}
+
+/// Store whether `lhs` can flow to `rhs` in `*out`.
+///
+/// # Safety
+///
+/// `lhs` and `rhs` must be null or live handles, and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn label_dclabel_can_flow_to(
+    lhs: *const DCLabel, rhs: *const DCLabel, out: *mut bool,
+) -> LabelStatus {
+    guard(|| {
+        let flows = deref(lhs)?.can_flow_to(deref(rhs)?);
+        write_out(out, flows)
+    })
+}
+
+/// Store the text form of `label` in `*out`, as a new string to be released with
+/// `label_string_free`.
+///
+/// # Safety
+///
+/// `label` must be null or a live handle, and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn label_dclabel_to_string(label: *const DCLabel, out: *mut *mut c_char) -> LabelStatus {
+    guard(|| {
+        // Principals containing control characters are escaped, so the text has no NUL.
+        let text = CString::new(deref(label)?.to_string()).expect("label text contains NUL");
+        write_out(out, text.into_raw())
+    })
+}
+
+/// Release a label. Null is ignored.
+///
+/// # Safety
+///
+/// `label` must be null or a handle returned by this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn label_dclabel_free(label: *mut DCLabel) {
+    if !label.is_null() {
+        let _ = panic::catch_unwind(|| drop(Box::from_raw(label)));
+    }
+}
+
+/// Release a string returned by `label_dclabel_to_string`. Null is ignored.
+///
+/// # Safety
+///
+/// `text` must be null or a string returned by this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn label_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    fn parse(s: &str) -> Result<*mut DCLabel, LabelStatus> {
+        let text = CString::new(s).unwrap();
+        let mut out = ptr::null_mut();
+        match unsafe { label_dclabel_parse(text.as_ptr(), &mut out) } {
+            LabelStatus::Ok => Ok(out),
+            status => Err(status),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let l = parse("<alice /\\ alice, True>").unwrap();
+        let mut text = ptr::null_mut();
+        assert_eq!(unsafe { label_dclabel_to_string(l, &mut text) }, LabelStatus::Ok);
+        assert_eq!(unsafe { CStr::from_ptr(text) }.to_str(), Ok("<(alice), True>"));
+        unsafe {
+            label_string_free(text);
+            label_dclabel_free(l);
+        }
+    }
+
+    #[test]
+    fn join_and_flow() {
+        let (alice, bob) = (parse("<alice, True>").unwrap(), parse("<bob, True>").unwrap());
+        let mut joined = ptr::null_mut();
+        let mut flows = false;
+        unsafe {
+            assert_eq!(label_dclabel_join(alice, bob, &mut joined), LabelStatus::Ok);
+            assert_eq!(*joined, "<alice /\\ bob, True>".parse().unwrap());
+            assert_eq!(label_dclabel_can_flow_to(alice, joined, &mut flows), LabelStatus::Ok);
+            assert!(flows);
+            assert_eq!(label_dclabel_can_flow_to(joined, bob, &mut flows), LabelStatus::Ok);
+            assert!(!flows);
+            for l in [alice, bob, joined] {
+                label_dclabel_free(l);
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("<alice"), Err(LabelStatus::ParseError));
+        let mut out = ptr::null_mut();
+        let invalid = b"<\xff, True>\0";
+        unsafe {
+            assert_eq!(label_dclabel_parse(invalid.as_ptr() as *const c_char, &mut out), LabelStatus::InvalidUtf8);
+            assert_eq!(label_dclabel_parse(ptr::null(), &mut out), LabelStatus::NullPointer);
+            assert_eq!(label_dclabel_meet(ptr::null(), ptr::null(), &mut out), LabelStatus::NullPointer);
+            label_dclabel_free(ptr::null_mut());
+        }
+        assert!(out.is_null());
+    }
+}
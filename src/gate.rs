@@ -0,0 +1,130 @@
+//! Gates: closures that can only be invoked by callers proving a privilege, as in DCLabels.
+//!
+//! A plugin can expose an entry point as a [`Gate`] instead of trusting an identity passed to it:
+//! the caller presents an [`AuditedPrivilege`], which it cannot make up, the gate checks its
+//! description against the gate's threshold, and the closure receives the description to decide
+//! what the caller may do.
+
+use std::fmt;
+
+use crate::audit::AuditedPrivilege;
+use crate::dclabel::Privilege;
+use crate::error::{Error, Result};
+
+/// A description of privileges, ordered by how much they allow.
+pub trait PrivilegeDescription {
+    /// This description is at least as powerful as `rhs`.
+    fn implies(&self, rhs: &Self) -> bool;
+}
+
+impl PrivilegeDescription for Privilege {
+    fn implies(&self, rhs: &Self) -> bool {
+        Privilege::implies(self, rhs)
+    }
+}
+
+type Body<P, A, R> = Box<dyn Fn(&P, A) -> R + Send + Sync>;
+
+/// A closure from `A` to `R` that callers must present a privilege described by `P` to invoke.
+pub struct Gate<P, A, R> {
+    threshold: P,
+    body: Body<P, A, R>,
+}
+
+impl<P: PrivilegeDescription, A, R> Gate<P, A, R> {
+    /// A gate that callers can only invoke with a privilege implying `threshold`. `body` receives
+    /// the caller's privilege description along with the argument.
+    pub fn new<F>(threshold: P, body: F) -> Self
+    where
+        F: Fn(&P, A) -> R + Send + Sync + 'static,
+    {
+        Gate { threshold, body: Box::new(body) }
+    }
+
+    pub fn threshold(&self) -> &P {
+        &self.threshold
+    }
+
+    /// Invoke the gate, presenting `privilege`.
+    ///
+    /// Fails with [`Error::InsufficientPrivilege`] without running the closure if `privilege`
+    /// does not imply the gate's threshold.
+    pub fn call(&self, privilege: &AuditedPrivilege<P>, arg: A) -> Result<R> {
+        let privilege = privilege.privilege();
+        if !privilege.implies(&self.threshold) {
+            return Err(Error::InsufficientPrivilege);
+        }
+        Ok((self.body)(privilege, arg))
+    }
+}
+
+impl<P: fmt::Debug, A, R> fmt::Debug for Gate<P, A, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Gate({:?})", self.threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::MemorySink;
+    use crate::dclabel::{Conjunction, DCLabel};
+    use std::sync::Arc;
+
+    fn description(s: &str) -> Privilege {
+        Privilege::new(s.parse::<Conjunction>().unwrap())
+    }
+
+    fn privilege(s: &str) -> AuditedPrivilege {
+        AuditedPrivilege::new(description(s), Arc::new(MemorySink::new()))
+    }
+
+    /// A plugin entry point that exports reports readable by whoever calls it.
+    fn export_gate() -> Gate<Privilege, DCLabel, bool> {
+        Gate::new(description("hr \\/ legal"), |p: &Privilege, report: DCLabel| {
+            report.can_flow_to_with(&DCLabel::public(), p)
+        })
+    }
+
+    #[test]
+    fn caller_meeting_threshold() {
+        let gate = export_gate();
+        let report: DCLabel = "<hr, True>".parse().unwrap();
+        assert_eq!(gate.call(&privilege("hr"), report.clone()), Ok(true));
+        assert_eq!(gate.call(&privilege("legal /\\ alice"), report), Ok(false));
+    }
+
+    #[test]
+    fn caller_below_threshold() {
+        let gate = export_gate();
+        assert_eq!(gate.call(&privilege("alice"), DCLabel::public()), Err(Error::InsufficientPrivilege));
+        assert_eq!(gate.call(&privilege("True"), DCLabel::public()), Err(Error::InsufficientPrivilege));
+    }
+
+    #[test]
+    fn body_sees_caller_privilege() {
+        let gate = Gate::new(Privilege::none(), |p: &Privilege, ()| p.to_string());
+        assert_eq!(gate.call(&privilege("alice /\\ bob"), ()), Ok("(alice) /\\ (bob)".to_string()));
+        assert_eq!(gate.threshold(), &Privilege::none());
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Role {
+        User,
+        Admin,
+    }
+
+    impl PrivilegeDescription for Role {
+        fn implies(&self, rhs: &Self) -> bool {
+            self == &Role::Admin || rhs == &Role::User
+        }
+    }
+
+    #[test]
+    fn custom_description() {
+        let root = crate::audit::PrivilegeRoot::for_testing(Arc::new(MemorySink::new()));
+        let gate = Gate::new(Role::Admin, |_: &Role, n: u32| n + 1);
+        assert_eq!(gate.call(&root.audit(Role::Admin), 1), Ok(2));
+        assert_eq!(gate.call(&root.audit(Role::User), 1), Err(Error::InsufficientPrivilege));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::Label;
+
+/// A value protected by a label.
+///
+/// The label is always observable, but the value itself should only be read through a
+/// [`Context`](crate::context::Context), which raises the current label to account for what was
+/// observed.
+///
+/// Pure computations on labeled values can instead be done inside the label, with
+/// [`Labeled::map`] and friends. The result is labeled with the join of the labels of its inputs,
+/// tracking flows per value without tainting the context.
+#[derive(Clone)]
+pub struct Labeled<L, T> {
+    label: L,
+    value: T,
+}
+
+impl<L, T> Labeled<L, T> {
+    pub fn new(label: L, value: T) -> Self {
+        Labeled { label, value }
+    }
+
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    pub(crate) fn value(&self) -> &T {
+        &self.value
+    }
+
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn into_value(self) -> T {
+        self.value
+    }
+
+    pub(crate) fn into_parts(self) -> (L, T) {
+        (self.label, self.value)
+    }
+
+    /// Apply `f` to the value, keeping the label.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Labeled<L, U> {
+        Labeled { label: self.label, value: f(self.value) }
+    }
+}
+
+impl<L: Label, T> Labeled<L, T> {
+    /// Combine two labeled values with `f`. The result is labeled with the join of both labels.
+    pub fn zip_with<U, R, F: FnOnce(T, U) -> R>(self, other: Labeled<L, U>, f: F) -> Labeled<L, R> {
+        Labeled { label: self.label.join(&other.label), value: f(self.value, other.value) }
+    }
+
+    /// Apply `f`, which produces a labeled value, to the value. The result is labeled with the
+    /// join of both labels.
+    pub fn and_then<U, F: FnOnce(T) -> Labeled<L, U>>(self, f: F) -> Labeled<L, U> {
+        let result = f(self.value);
+        Labeled { label: self.label.join(&result.label), value: result.value }
+    }
+
+    /// Collect labeled values into a single labeled collection, labeled with the join of all
+    /// their labels and `floor`.
+    ///
+    /// `floor` is the label of an empty collection, usually the bottom of the lattice.
+    pub fn sequence<C, I>(floor: L, iter: I) -> Labeled<L, C>
+    where
+        I: IntoIterator<Item = Labeled<L, T>>,
+        C: core::iter::FromIterator<T>,
+    {
+        let mut label = floor;
+        let value = iter.into_iter().map(|labeled| {
+            label = label.join(&labeled.label);
+            labeled.value
+        }).collect();
+        Labeled { label, value }
+    }
+}
+
+impl<L: core::fmt::Debug, T> core::fmt::Debug for Labeled<L, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> Result<(), core::fmt::Error> {
+        write!(f, "Labeled({:?}, <hidden>)", self.label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twolevel::TwoLevel;
+
+    #[test]
+    fn map_keeps_label() {
+        let secret = Labeled::new(TwoLevel::High, 2).map(|n| n * 3);
+        assert_eq!((secret.label(), secret.value()), (&TwoLevel::High, &6));
+    }
+
+    #[test]
+    fn sequence_joins_labels() {
+        let values = vec![Labeled::new(TwoLevel::Low, 1), Labeled::new(TwoLevel::High, 2)];
+        let all: Labeled<_, Vec<_>> = Labeled::sequence(TwoLevel::Low, values.clone());
+        assert_eq!((all.label(), all.value()), (&TwoLevel::High, &vec![1, 2]));
+
+        let first: Labeled<_, Vec<_>> = Labeled::sequence(TwoLevel::Low, values.into_iter().take(1));
+        assert_eq!((first.label(), first.value()), (&TwoLevel::Low, &vec![1]));
+        let empty: Labeled<_, Vec<i32>> = Labeled::sequence(TwoLevel::Low, Vec::new());
+        assert_eq!(empty.label(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn and_then_joins_labels() {
+        let index = Labeled::new(TwoLevel::Low, 1);
+        let table = [Labeled::new(TwoLevel::Low, 'a'), Labeled::new(TwoLevel::High, 'b')];
+        let found = index.and_then(|i| table[i].clone());
+        assert_eq!((found.label(), found.value()), (&TwoLevel::High, &'b'));
+    }
+}
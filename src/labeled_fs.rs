@@ -0,0 +1,418 @@
+//! A filesystem in which every file and directory carries a [`DCLabel`].
+//!
+//! A `LabeledFs` is rooted at a directory on disk. Labels are stored in the `user.dclabel`
+//! extended attribute when the `xattr` feature is enabled and the underlying filesystem supports
+//! it, and otherwise in a sidecar file: `.<name>.label` next to a file, or `.label` inside a
+//! directory. Storing a label in one place removes it from the other, and a path whose xattr and
+//! sidecar disagree cannot be used.
+//!
+//! As in Hails and COWL, looking up a path observes every directory along it, so each
+//! directory's label taints the caller. The label of an entry must be at least as restrictive as
+//! the directory containing it, creating an entry is a write to the directory, and relabeling an
+//! existing file may only raise its label.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::Label;
+use crate::context::Context;
+use crate::dclabel::{DCLabel, ParseError};
+
+const SIDECAR: &str = ".label";
+#[cfg(feature = "xattr")]
+const XATTR: &str = "user.dclabel";
+
+/// Errors returned by [`LabeledFs`] operations, and by the stores of [`store`](crate::store).
+#[derive(Debug)]
+pub enum Error {
+    /// The operation violates the information flow policy.
+    Flow(crate::Error),
+    /// The path is absolute, or contains `.`, `..`, or a component starting with `.`.
+    InvalidPath(PathBuf),
+    /// A stored label could not be parsed.
+    Parse(ParseError),
+    /// A stored record could not be decoded.
+    Corrupt(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Flow(e) => e.fmt(f),
+            Error::InvalidPath(p) => write!(f, "invalid path {:?}", p),
+            Error::Parse(e) => write!(f, "invalid stored label: {}", e),
+            Error::Corrupt(msg) => write!(f, "corrupt record: {}", msg),
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Self {
+        Error::Flow(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A labeled directory tree rooted at a path on disk.
+pub struct LabeledFs {
+    root: PathBuf,
+}
+
+impl LabeledFs {
+    /// Create a new labeled filesystem, creating the root directory and labeling it `label`.
+    ///
+    /// Fails if the root directory already carries a label, rather than relabeling it.
+    pub fn create<P: Into<PathBuf>>(root: P, label: &DCLabel) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        if load_label(&root, true).is_ok() {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists).into());
+        }
+        store_label(&root, true, label)?;
+        Ok(LabeledFs { root })
+    }
+
+    /// Open an existing labeled filesystem.
+    pub fn open<P: Into<PathBuf>>(root: P) -> Result<Self> {
+        let root = root.into();
+        load_label(&root, true)?;
+        Ok(LabeledFs { root })
+    }
+
+    /// Read the label of `path`, tainting `ctx` with the labels of the directories containing it.
+    pub fn label(&self, ctx: &mut Context<DCLabel>, path: &Path) -> Result<DCLabel> {
+        let (full, _) = self.lookup(ctx, path)?;
+        load_label(&full, full.is_dir())
+    }
+
+    /// Read the contents of the file at `path`.
+    ///
+    /// Fails without reading anything unless the clearance of `ctx` dominates the file's label;
+    /// otherwise `ctx` is tainted with it.
+    pub fn read(&self, ctx: &mut Context<DCLabel>, path: &Path) -> Result<Vec<u8>> {
+        let (full, _) = self.lookup(ctx, path)?;
+        ctx.taint(&load_label(&full, false)?)?;
+        Ok(fs::read(full)?)
+    }
+
+    /// Write `data` to the file at `path`, labeling it `label`.
+    ///
+    /// Creating the file is a write to its directory, and `label` must be at least as restrictive
+    /// as the directory's. Overwriting an existing file is a write to it, and `label` must be at
+    /// least as restrictive as its current label.
+    ///
+    /// The label is stored before the data is written, so the new data is never readable under
+    /// the old label, even if the write fails part way.
+    pub fn write(&self, ctx: &mut Context<DCLabel>, path: &Path, label: &DCLabel, data: &[u8]) -> Result<()> {
+        let (full, parent) = self.lookup(ctx, path)?;
+        self.guard_entry(ctx, &full, &parent, label)?;
+        if !full.exists() {
+            fs::File::create(&full)?;
+            if let Err(e) = store_label(&full, false, label) {
+                let _ = fs::remove_file(&full);
+                return Err(e);
+            }
+        } else {
+            store_label(&full, false, label)?;
+        }
+        Ok(fs::write(&full, data)?)
+    }
+
+    /// Create the directory `path`, labeling it `label`.
+    ///
+    /// Subject to the same checks as creating a file with [`LabeledFs::write`].
+    pub fn create_dir(&self, ctx: &mut Context<DCLabel>, path: &Path, label: &DCLabel) -> Result<()> {
+        let (full, parent) = self.lookup(ctx, path)?;
+        if full.exists() {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists).into());
+        }
+        self.guard_entry(ctx, &full, &parent, label)?;
+        fs::create_dir(&full)?;
+        store_label(&full, true, label)
+    }
+
+    /// List the names of the entries in the directory `path`, tainting `ctx` with its label.
+    pub fn read_dir(&self, ctx: &mut Context<DCLabel>, path: &Path) -> Result<Vec<String>> {
+        let (full, _) = self.lookup(ctx, path)?;
+        ctx.taint(&load_label(&full, true)?)?;
+        let mut names = Vec::new();
+        for entry in fs::read_dir(full)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if !name.starts_with('.') {
+                names.push(name);
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Resolve `path` to a location on disk, tainting `ctx` with the label of every directory
+    /// traversed. Returns the resolved path and the label of its parent directory.
+    fn lookup(&self, ctx: &mut Context<DCLabel>, path: &Path) -> Result<(PathBuf, DCLabel)> {
+        let mut names = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(name) if !name.to_string_lossy().starts_with('.') => names.push(name),
+                _ => return Err(Error::InvalidPath(path.to_path_buf())),
+            }
+        }
+
+        let mut full = self.root.clone();
+        let mut parent = load_label(&full, true)?;
+        let mut names = names.into_iter().peekable();
+        while let Some(name) = names.next() {
+            ctx.taint(&parent)?;
+            full.push(name);
+            if names.peek().is_some() {
+                parent = load_label(&full, true)?;
+            }
+        }
+        Ok((full, parent))
+    }
+
+    fn guard_entry(&self, ctx: &Context<DCLabel>, full: &Path, parent: &DCLabel, label: &DCLabel) -> Result<()> {
+        if full.exists() {
+            let old = load_label(full, full.is_dir())?;
+            ctx.guard_write(&old)?;
+            if !old.can_flow_to(label) {
+                return Err(crate::Error::FlowViolation.into());
+            }
+        } else {
+            ctx.guard_write(parent)?;
+            if !parent.can_flow_to(label) {
+                return Err(crate::Error::FlowViolation.into());
+            }
+        }
+        ctx.guard_write(label)?;
+        Ok(())
+    }
+}
+
+fn sidecar(path: &Path, is_dir: bool) -> PathBuf {
+    if is_dir {
+        path.join(SIDECAR)
+    } else {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!(".{}{}", name, SIDECAR))
+    }
+}
+
+fn store_label(path: &Path, is_dir: bool, label: &DCLabel) -> Result<()> {
+    let text = label.to_string();
+    #[cfg(feature = "xattr")]
+    {
+        if xattr::set(path, XATTR, text.as_bytes()).is_ok() {
+            return match fs::remove_file(sidecar(path, is_dir)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+    }
+    fs::write(sidecar(path, is_dir), text)?;
+    // If a stale xattr cannot be removed either, `load_label` refuses the path.
+    #[cfg(feature = "xattr")]
+    let _ = xattr::remove(path, XATTR);
+    Ok(())
+}
+
+fn load_label(path: &Path, is_dir: bool) -> Result<DCLabel> {
+    let sidecar = match fs::read_to_string(sidecar(path, is_dir)) {
+        Ok(text) => Some(text.parse()?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    #[cfg(feature = "xattr")]
+    {
+        if let Ok(Some(bytes)) = xattr::get(path, XATTR) {
+            let label: DCLabel = String::from_utf8_lossy(&bytes).parse()?;
+            return match sidecar {
+                Some(other) if other != label => {
+                    Err(Error::Corrupt(format!("label xattr and sidecar of {:?} disagree", path)))
+                }
+                _ => Ok(label),
+            };
+        }
+    }
+    sidecar.ok_or_else(|| io::Error::from(io::ErrorKind::NotFound).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn temp_root() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!("label-fs-{}-{}", std::process::id(), n));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn write_then_read() {
+        let root = temp_root();
+        let lfs = LabeledFs::create(&root, &DCLabel::public()).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), label("<alice, True>")).unwrap();
+
+        lfs.write(&mut ctx, Path::new("notes"), &label("<alice, True>"), b"hello").unwrap();
+        assert_eq!(ctx.label(), &DCLabel::public());
+        assert_eq!(lfs.read(&mut ctx, Path::new("notes")).unwrap(), b"hello");
+        assert_eq!(ctx.label(), &label("<alice, True>"));
+
+        let lfs = LabeledFs::open(&root).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        assert_eq!(lfs.label(&mut ctx, Path::new("notes")).unwrap(), label("<alice, True>"));
+        assert_eq!(lfs.read_dir(&mut ctx, Path::new("")).unwrap(), vec!["notes".to_string()]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn read_requires_clearance() {
+        let root = temp_root();
+        let lfs = LabeledFs::create(&root, &DCLabel::public()).unwrap();
+        let mut writer = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        lfs.write(&mut writer, Path::new("secret"), &label("<alice, True>"), b"x").unwrap();
+
+        let mut reader = Context::new(DCLabel::public(), label("<bob, True>")).unwrap();
+        match lfs.read(&mut reader, Path::new("secret")) {
+            Err(Error::Flow(crate::Error::ClearanceViolation)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        assert_eq!(reader.label(), &DCLabel::public());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn cannot_lower_label() {
+        let root = temp_root();
+        let lfs = LabeledFs::create(&root, &DCLabel::public()).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let path = Path::new("secret");
+        lfs.write(&mut ctx, path, &label("<alice, True>"), b"x").unwrap();
+
+        match lfs.write(&mut ctx, path, &DCLabel::public(), b"y") {
+            Err(Error::Flow(crate::Error::FlowViolation)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        lfs.write(&mut ctx, path, &label("<alice /\\ bob, True>"), b"z").unwrap();
+        assert_eq!(lfs.label(&mut ctx, path).unwrap(), label("<alice /\\ bob, True>"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn failed_label_store_removes_new_file() {
+        let root = temp_root();
+        let lfs = LabeledFs::create(&root, &DCLabel::public()).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        lfs.write(&mut ctx, Path::new("notes"), &DCLabel::public(), b"old").unwrap();
+
+        // A directory where the sidecar belongs can be neither written nor removed.
+        fs::create_dir(root.join(".fresh.label")).unwrap();
+        assert!(lfs.write(&mut ctx, Path::new("fresh"), &label("<alice, True>"), b"new").is_err());
+        assert!(!root.join("fresh").exists());
+
+        let mut public = Context::new(DCLabel::public(), DCLabel::public()).unwrap();
+        assert_eq!(lfs.read(&mut public, Path::new("notes")).unwrap(), b"old");
+        assert_eq!(lfs.read_dir(&mut public, Path::new("")).unwrap(), vec!["notes".to_string()]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(feature = "xattr")]
+    #[test]
+    fn stale_label_fails_closed() {
+        let root = temp_root();
+        let lfs = LabeledFs::create(&root, &DCLabel::public()).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let secret = label("<alice, True>");
+        lfs.write(&mut ctx, Path::new("notes"), &DCLabel::public(), b"old").unwrap();
+        if xattr::get(root.join("notes"), XATTR).ok().flatten().is_none() {
+            // Labels are kept in sidecars on this filesystem.
+            return fs::remove_dir_all(root).unwrap();
+        }
+
+        fs::write(root.join(".notes.label"), secret.to_string()).unwrap();
+        assert!(matches!(lfs.read(&mut ctx, Path::new("notes")), Err(Error::Corrupt(_))));
+        assert!(matches!(lfs.write(&mut ctx, Path::new("notes"), &secret, b"new"), Err(Error::Corrupt(_))));
+
+        // Storing a label through the xattr removes the sidecar.
+        store_label(&root.join("notes"), false, &secret).unwrap();
+        assert!(!root.join(".notes.label").exists());
+        assert_eq!(lfs.label(&mut ctx, Path::new("notes")).unwrap(), secret);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn create_does_not_relabel() {
+        let root = temp_root();
+        LabeledFs::create(&root, &label("<alice, True>")).unwrap();
+        match LabeledFs::create(&root, &DCLabel::public()) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::AlreadyExists),
+            r => panic!("unexpected result {:?}", r.map(|_| ())),
+        }
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let lfs = LabeledFs::open(&root).unwrap();
+        lfs.read_dir(&mut ctx, Path::new("")).unwrap();
+        assert_eq!(ctx.label(), &label("<alice, True>"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn directory_bounds_entries() {
+        let root = temp_root();
+        let lfs = LabeledFs::create(&root, &DCLabel::public()).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        lfs.create_dir(&mut ctx, Path::new("hr"), &label("<hr, True>")).unwrap();
+
+        match lfs.write(&mut ctx, Path::new("hr/payroll"), &DCLabel::public(), b"x") {
+            Err(Error::Flow(crate::Error::FlowViolation)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+
+        // Traversing `hr` taints the context, so nothing more public can be written afterwards.
+        lfs.write(&mut ctx, Path::new("hr/payroll"), &label("<hr, True>"), b"x").unwrap();
+        assert_eq!(ctx.label(), &label("<hr, True>"));
+        match lfs.write(&mut ctx, Path::new("leak"), &DCLabel::public(), b"x") {
+            Err(Error::Flow(crate::Error::FlowViolation)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let root = temp_root();
+        let lfs = LabeledFs::create(&root, &DCLabel::public()).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        for p in &["../escape", "/etc/passwd", ".label", "a/../b"] {
+            match lfs.read(&mut ctx, Path::new(p)) {
+                Err(Error::InvalidPath(_)) => {}
+                r => panic!("unexpected result for {}: {:?}", p, r),
+            }
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::Label;
+use crate::context::Context;
+use crate::error::Result;
+
+/// A mutable cell protected by a fixed label, after LIO's `LIORef`.
+///
+/// Clones share the same cell, so a `LabeledRef` can hold state shared between computations.
+/// Reading taints the reader's current label with the cell's label, and writing is subject to
+/// [`Context::guard_write`], so a computation that has observed data above the cell's label can
+/// no longer change it.
+pub struct LabeledRef<L, T> {
+    label: L,
+    cell: Arc<RwLock<T>>,
+}
+
+impl<L: Label + Clone, T> LabeledRef<L, T> {
+    /// Create a cell labeled `label`, holding `value`.
+    ///
+    /// Creating the cell is a write to it, so `label` must satisfy [`Context::guard_write`].
+    pub fn new(ctx: &Context<L>, label: L, value: T) -> Result<Self> {
+        ctx.guard_write(&label)?;
+        Ok(LabeledRef { label, cell: Arc::new(RwLock::new(value)) })
+    }
+
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    /// Read the contents, tainting `ctx` with the cell's label.
+    pub fn read(&self, ctx: &mut Context<L>) -> Result<T>
+    where
+        T: Clone,
+    {
+        ctx.taint(&self.label)?;
+        Ok(self.cell.read().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+
+    /// Replace the contents with `value`.
+    ///
+    /// Fails with [`Error::FlowViolation`](crate::Error::FlowViolation) if the current label of
+    /// `ctx` cannot flow to the cell's label, or
+    /// [`Error::ClearanceViolation`](crate::Error::ClearanceViolation) if the cell's label is
+    /// above the clearance of `ctx`.
+    pub fn write(&self, ctx: &Context<L>, value: T) -> Result<()> {
+        ctx.guard_write(&self.label)?;
+        *self.cell.write().unwrap_or_else(PoisonError::into_inner) = value;
+        Ok(())
+    }
+
+    /// Update the contents in place with `f`, atomically.
+    ///
+    /// This both reads and writes the cell: `ctx` is tainted with the cell's label, and the
+    /// write must then satisfy [`Context::guard_write`]. Neither happens if the checks fail.
+    pub fn modify<R, F: FnOnce(&mut T) -> R>(&self, ctx: &mut Context<L>, f: F) -> Result<R> {
+        ctx.guard_write(&self.label)?;
+        ctx.taint(&self.label)?;
+        Ok(f(&mut self.cell.write().unwrap_or_else(PoisonError::into_inner)))
+    }
+}
+
+impl<L: Clone, T> Clone for LabeledRef<L, T> {
+    fn clone(&self) -> Self {
+        LabeledRef { label: self.label.clone(), cell: self.cell.clone() }
+    }
+}
+
+impl<L: std::fmt::Debug, T> std::fmt::Debug for LabeledRef<L, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "LabeledRef({:?}, <hidden>)", self.label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use crate::twolevel::TwoLevel;
+
+    fn ctx(label: TwoLevel) -> Context<TwoLevel> {
+        Context::new(label, TwoLevel::High).unwrap()
+    }
+
+    #[test]
+    fn read_taints() {
+        let secret = LabeledRef::new(&ctx(TwoLevel::Low), TwoLevel::High, 7).unwrap();
+        let mut reader = ctx(TwoLevel::Low);
+        assert_eq!(secret.read(&mut reader), Ok(7));
+        assert_eq!(reader.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn high_handler_cannot_corrupt_low_state() {
+        let counter = LabeledRef::new(&ctx(TwoLevel::Low), TwoLevel::Low, 0).unwrap();
+        let shared = counter.clone();
+        let mut handler = ctx(TwoLevel::Low);
+        handler.taint(&TwoLevel::High).unwrap();
+
+        assert_eq!(shared.write(&handler, 1), Err(Error::FlowViolation));
+        assert_eq!(shared.modify(&mut handler, |n| *n += 1), Err(Error::FlowViolation));
+        assert_eq!(counter.read(&mut ctx(TwoLevel::Low)), Ok(0));
+    }
+
+    #[test]
+    fn low_handler_updates_shared_state() {
+        let counter = LabeledRef::new(&ctx(TwoLevel::Low), TwoLevel::Low, 0).unwrap();
+        let handlers: Vec<_> = (0..4).map(|_| {
+            let counter = counter.clone();
+            std::thread::spawn(move || counter.modify(&mut ctx(TwoLevel::Low), |n| *n += 1).unwrap())
+        }).collect();
+        for h in handlers {
+            h.join().unwrap();
+        }
+        assert_eq!(counter.read(&mut ctx(TwoLevel::Low)), Ok(4));
+    }
+
+    #[test]
+    fn clearance() {
+        let low = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        assert!(LabeledRef::new(&low, TwoLevel::High, 0).is_err());
+
+        let secret = LabeledRef::new(&ctx(TwoLevel::Low), TwoLevel::High, 0).unwrap();
+        let mut reader = low.clone();
+        assert_eq!(secret.read(&mut reader), Err(Error::ClearanceViolation));
+        assert_eq!(secret.write(&low, 1), Err(Error::ClearanceViolation));
+        assert_eq!(reader.label(), &TwoLevel::Low);
+    }
+}
@@ -0,0 +1,428 @@
+//! Strings with a label per byte range.
+//!
+//! Text assembled from many sources, such as a rendered template, does not need to be labeled
+//! with the join of all of them: a [`LabeledString`] keeps the label of each piece as it is
+//! concatenated, sliced and formatted, so the label of any part of the result is known. Reading
+//! the text goes through a [`Context`], or through [`LabeledString::redact`] to drop the parts a
+//! reader is not cleared for.
+//!
+//! Where the pieces start and end depends on the length of each of them, so the length of the
+//! text and the ranges of its pieces are also only read through a [`Context`], which is tainted
+//! with the labels of the whole text.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Add, AddAssign, Bound, Range, RangeBounds};
+
+use crate::Label;
+use crate::context::Context;
+use crate::error::Result;
+use crate::labeled::Labeled;
+
+#[derive(Clone)]
+struct Span<L> {
+    end: usize,
+    label: L,
+}
+
+/// A string whose byte ranges each carry a label.
+///
+/// Adjacent ranges with equal labels are merged, so the ranges are the coarsest split of the
+/// text by label.
+#[derive(Clone)]
+pub struct LabeledString<L> {
+    text: String,
+    spans: Vec<Span<L>>,
+}
+
+impl<L: Label + Clone> LabeledString<L> {
+    pub fn new() -> Self {
+        LabeledString { text: String::new(), spans: Vec::new() }
+    }
+
+    /// A string of `text`, labeled `label` throughout.
+    pub fn labeled(label: L, text: &str) -> Self {
+        let mut s = LabeledString::new();
+        s.push_str(label, text);
+        s
+    }
+
+    /// The length of the text in bytes, tainting `ctx` with the join of its labels.
+    pub fn len(&self, ctx: &mut Context<L>) -> Result<usize> {
+        self.taint_all(ctx)?;
+        Ok(self.text.len())
+    }
+
+    /// Whether the text is empty, tainting `ctx` with the join of its labels.
+    pub fn is_empty(&self, ctx: &mut Context<L>) -> Result<bool> {
+        self.taint_all(ctx)?;
+        Ok(self.text.is_empty())
+    }
+
+    fn taint_all(&self, ctx: &mut Context<L>) -> Result<()> {
+        if let Some(label) = self.label_upper_bound() {
+            ctx.taint(&label)?;
+        }
+        Ok(())
+    }
+
+    fn push_span(&mut self, end: usize, label: L) {
+        match self.spans.last_mut() {
+            Some(last) if last.label.can_flow_to(&label) && label.can_flow_to(&last.label) => last.end = end,
+            _ => self.spans.push(Span { end, label }),
+        }
+    }
+
+    /// Append `text`, labeled `label`.
+    pub fn push_str(&mut self, label: L, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.text.push_str(text);
+        self.push_span(self.text.len(), label);
+    }
+
+    /// Append `other`, keeping its labels.
+    pub fn append(&mut self, other: &LabeledString<L>) {
+        let offset = self.text.len();
+        self.text.push_str(&other.text);
+        for span in &other.spans {
+            self.push_span(offset + span.end, span.label.clone());
+        }
+    }
+
+    /// The byte ranges of the text and their labels, in order, tainting `ctx` with the join of
+    /// its labels.
+    pub fn spans<'a>(&'a self, ctx: &mut Context<L>) -> Result<impl Iterator<Item = (Range<usize>, &'a L)> + 'a> {
+        self.taint_all(ctx)?;
+        Ok(self.runs())
+    }
+
+    fn runs(&self) -> impl Iterator<Item = (Range<usize>, &L)> {
+        let starts = core::iter::once(0).chain(self.spans.iter().map(|span| span.end));
+        starts.zip(&self.spans).map(|(start, span)| (start..span.end, &span.label))
+    }
+
+    fn bounds<R: RangeBounds<usize>>(&self, range: R) -> Range<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i + 1,
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.text.len(),
+        };
+        // Panics like string slicing on out of bounds ranges and ranges splitting a character.
+        let _ = &self.text[start..end];
+        start..end
+    }
+
+    /// The bytes in `range`, keeping their labels.
+    ///
+    /// Panics if `range` is out of bounds or does not fall on character boundaries, as for
+    /// `str` slicing.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Self {
+        let range = self.bounds(range);
+        let mut s = LabeledString::new();
+        for (span, label) in self.runs() {
+            let start = span.start.max(range.start);
+            let end = span.end.min(range.end);
+            if start < end {
+                s.push_str(label.clone(), &self.text[start..end]);
+            }
+        }
+        s
+    }
+
+    /// The join of the labels of the bytes in `range`, or `None` if it is empty, tainting `ctx`
+    /// with the join of the labels of the whole text.
+    ///
+    /// Panics under the same conditions as [`LabeledString::slice`].
+    pub fn label_of<R: RangeBounds<usize>>(&self, ctx: &mut Context<L>, range: R) -> Result<Option<L>> {
+        self.taint_all(ctx)?;
+        Ok(self.join_labels(range))
+    }
+
+    fn join_labels<R: RangeBounds<usize>>(&self, range: R) -> Option<L> {
+        let range = self.bounds(range);
+        self.runs()
+            .filter(|(span, _)| span.start.max(range.start) < span.end.min(range.end))
+            .map(|(_, label)| label.clone())
+            .fold(None, |bound, label| match bound {
+                Some(bound) => Some(label.join(&bound)),
+                None => Some(label),
+            })
+    }
+
+    /// The join of the labels of the whole text, or `None` if it is empty.
+    pub fn label_upper_bound(&self) -> Option<L> {
+        self.join_labels(..)
+    }
+
+    /// Replace every run of text whose label cannot flow to `clearance` with `placeholder`.
+    ///
+    /// Only the text is dropped. A placeholder still shows where the text it replaces was, so it
+    /// is labeled with the join of that text's labels, and reading it requires the same clearance.
+    pub fn redact(&self, clearance: &L, placeholder: &str) -> Self {
+        let mut s = LabeledString::new();
+        let mut redacted: Option<L> = None;
+        for (span, label) in self.runs() {
+            if label.can_flow_to(clearance) {
+                if let Some(label) = redacted.take() {
+                    s.push_str(label, placeholder);
+                }
+                s.push_str(label.clone(), &self.text[span]);
+            } else {
+                redacted = Some(match redacted {
+                    Some(bound) => bound.join(label),
+                    None => label.clone(),
+                });
+            }
+        }
+        if let Some(label) = redacted {
+            s.push_str(label, placeholder);
+        }
+        s
+    }
+
+    /// Read the text, tainting `ctx` with the join of its labels.
+    pub fn read(&self, ctx: &mut Context<L>) -> Result<String> {
+        self.taint_all(ctx)?;
+        Ok(self.text.clone())
+    }
+
+    /// Substitute `args` for each `{}` in `template`, whose own text is labeled `label`. `{{`
+    /// and `}}` stand for literal braces. This is what
+    /// [`labeled_format!`](crate::labeled_format) expands to.
+    ///
+    /// Returns `None` if the number of `{}` differs from the number of arguments.
+    pub fn format(label: L, template: &str, args: &[&LabeledString<L>]) -> Option<Self> {
+        if placeholders(template) != args.len() {
+            return None;
+        }
+        let mut s = LabeledString::new();
+        let mut args = args.iter();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('{', Some('}')) => {
+                    chars.next();
+                    s.push_str(label.clone(), &literal);
+                    literal.clear();
+                    if let Some(arg) = args.next() {
+                        s.append(arg);
+                    }
+                }
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    literal.push(c);
+                }
+                _ => literal.push(c),
+            }
+        }
+        s.push_str(label, &literal);
+        Some(s)
+    }
+}
+
+/// The number of `{}` placeholders in a [`LabeledString::format`] template.
+#[doc(hidden)]
+pub const fn placeholders(template: &str) -> usize {
+    let bytes = template.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if i + 1 < bytes.len() && (bytes[i] == b'{' || bytes[i] == b'}') {
+            if bytes[i] == b'{' && bytes[i + 1] == b'}' {
+                count += 1;
+                i += 1;
+            } else if bytes[i + 1] == bytes[i] {
+                i += 1;
+            }
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Build a [`LabeledString`] from a template, like `format!`.
+///
+/// The first argument is the label of the template text, and each `{}` in the template, a string
+/// literal, is replaced by the next argument, a `LabeledString` that keeps its own labels. As
+/// with `format!`, a template whose placeholders do not match the arguments does not compile.
+/// See [`LabeledString::format`].
+///
+/// ```
+/// use label::context::Context;
+/// use label::labeled_format;
+/// use label::labeled_string::LabeledString;
+/// use label::twolevel::TwoLevel;
+///
+/// let name = LabeledString::labeled(TwoLevel::High, "Eve");
+/// let greeting = labeled_format!(TwoLevel::Low, "Hello, {}!", name);
+/// let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+/// assert_eq!(greeting.label_of(&mut ctx, ..7), Ok(Some(TwoLevel::Low)));
+/// assert_eq!(greeting.label_of(&mut ctx, 7..10), Ok(Some(TwoLevel::High)));
+/// assert_eq!(ctx.label(), &TwoLevel::High);
+/// ```
+#[macro_export]
+macro_rules! labeled_format {
+    (@unit $arg:expr) => { () };
+    ($label:expr, $template:literal $(, $arg:expr)* $(,)?) => {{
+        const _: () = assert!(
+            $crate::labeled_string::placeholders($template) == [$($crate::labeled_format!(@unit $arg)),*].len(),
+            "the number of placeholders in the template differs from the number of arguments",
+        );
+        match $crate::labeled_string::LabeledString::format($label, $template, &[$(&$arg),*]) {
+            Some(s) => s,
+            None => unreachable!(),
+        }
+    }};
+}
+
+impl<L: Label + Clone> Default for LabeledString<L> {
+    fn default() -> Self {
+        LabeledString::new()
+    }
+}
+
+impl<L: Label + Clone> From<Labeled<L, String>> for LabeledString<L> {
+    fn from(labeled: Labeled<L, String>) -> Self {
+        let (label, text) = labeled.into_parts();
+        LabeledString::labeled(label, &text)
+    }
+}
+
+impl<L: Label + Clone> Add for LabeledString<L> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self.append(&rhs);
+        self
+    }
+}
+
+impl<L: Label + Clone> AddAssign for LabeledString<L> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.append(&rhs);
+    }
+}
+
+impl<L: Label + Clone + fmt::Debug> fmt::Debug for LabeledString<L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("LabeledString(")?;
+        f.debug_list().entries(self.runs().map(|(_, label)| label)).finish()?;
+        f.write_str(", <hidden>)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dclabel::DCLabel;
+    use crate::twolevel::TwoLevel;
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    fn page() -> LabeledString<DCLabel> {
+        let name = LabeledString::labeled(label("<alice, True>"), "Alice");
+        let balance = LabeledString::labeled(label("<alice /\\ bank, True>"), "$12");
+        labeled_format!(DCLabel::public(), "Hi {}, your balance is {}. {{ok}}", name, balance)
+    }
+
+    #[test]
+    fn format_keeps_labels() {
+        let page = page();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let spans: Vec<_> = page.spans(&mut ctx).unwrap().map(|(range, label)| (range, label.to_string())).collect();
+        assert_eq!(spans, vec![
+            (0..3, DCLabel::public().to_string()),
+            (3..8, label("<alice, True>").to_string()),
+            (8..26, DCLabel::public().to_string()),
+            (26..29, label("<alice /\\ bank, True>").to_string()),
+            (29..35, DCLabel::public().to_string()),
+        ]);
+        assert_eq!(ctx.label(), &label("<alice /\\ bank, True>"));
+        assert_eq!(page.read(&mut ctx), Ok("Hi Alice, your balance is $12. {ok}".to_string()));
+    }
+
+    #[test]
+    fn label_of_subrange() {
+        let page = page();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        assert_eq!(page.label_of(&mut ctx, ..3), Ok(Some(DCLabel::public())));
+        assert_eq!(ctx.label(), &label("<alice /\\ bank, True>"));
+        assert_eq!(page.label_of(&mut ctx, 0..4), Ok(Some(label("<alice, True>"))));
+        assert_eq!(page.label_of(&mut ctx, 4..4), Ok(None));
+        assert_eq!(page.label_upper_bound(), Some(label("<alice /\\ bank, True>")));
+    }
+
+    #[test]
+    fn lengths_require_clearance() {
+        let page = page();
+        let mut alice = Context::new(DCLabel::public(), label("<alice, True>")).unwrap();
+        assert_eq!(page.len(&mut alice), Err(crate::Error::ClearanceViolation));
+        assert!(page.spans(&mut alice).is_err());
+        assert_eq!(page.label_of(&mut alice, ..3), Err(crate::Error::ClearanceViolation));
+        assert_eq!(alice.label(), &DCLabel::public());
+        let s = LabeledString::labeled(TwoLevel::Low, "a") + LabeledString::labeled(TwoLevel::High, "bc");
+        assert_eq!(format!("{:?}", s), "LabeledString([Low, High], <hidden>)");
+    }
+
+    #[test]
+    fn slice_and_concatenate() {
+        let page = page();
+        let name = page.slice(3..8);
+        assert_eq!(name.label_upper_bound(), Some(label("<alice, True>")));
+        let joined = page.slice(..3) + name;
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        assert_eq!(joined.spans(&mut ctx).unwrap().count(), 2);
+        assert_eq!(joined.len(&mut ctx), Ok(8));
+
+        let mut merged = LabeledString::labeled(TwoLevel::Low, "a");
+        merged += LabeledString::labeled(TwoLevel::Low, "b");
+        merged.push_str(TwoLevel::High, "");
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        assert_eq!(merged.spans(&mut ctx).unwrap().collect::<Vec<_>>(), vec![(0..2, &TwoLevel::Low)]);
+        assert_eq!(merged.is_empty(&mut ctx), Ok(false));
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_inside_character() {
+        LabeledString::labeled(TwoLevel::Low, "é").slice(1..);
+    }
+
+    #[test]
+    fn redact() {
+        let clearance = label("<alice, True>");
+        let redacted = page().redact(&clearance, "[redacted]");
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        assert_eq!(redacted.read(&mut ctx), Ok("Hi Alice, your balance is [redacted]. {ok}".to_string()));
+        assert_eq!(redacted.slice(26..36).label_upper_bound(), Some(label("<alice /\\ bank, True>")));
+
+        let mut alice = Context::new(DCLabel::public(), clearance).unwrap();
+        assert_eq!(redacted.read(&mut alice), Err(crate::Error::ClearanceViolation));
+        assert_eq!(redacted.slice(..26).read(&mut alice), Ok("Hi Alice, your balance is ".to_string()));
+
+        let mut public = Context::new(DCLabel::public(), DCLabel::public()).unwrap();
+        let redacted = page().redact(&DCLabel::public(), "***");
+        assert_eq!(redacted.slice(..3).read(&mut public), Ok("Hi ".to_string()));
+        assert_eq!(redacted.slice(3..6).label_upper_bound(), Some(label("<alice, True>")));
+    }
+
+    #[test]
+    fn format_argument_count() {
+        let one = LabeledString::labeled(TwoLevel::Low, "one");
+        assert!(LabeledString::format(TwoLevel::Low, "{} and {}", &[&one]).is_none());
+        assert!(LabeledString::format(TwoLevel::Low, "{{}}", &[&one]).is_none());
+        assert!(LabeledString::format(TwoLevel::Low, "{} and {{}}", &[&one]).is_some());
+        assert_eq!(placeholders("{{{}}}é{}"), 2);
+    }
+}
@@ -1,10 +1,50 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 #[cfg(test)]
 #[macro_use]
 extern crate quickcheck;
 
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod channel;
+#[cfg(feature = "alloc")]
+pub mod collections;
+pub mod context;
+#[cfg(feature = "alloc")]
 pub mod dclabel;
+#[cfg(feature = "alloc")]
+pub mod faceted;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod gate;
+pub mod labeled;
+#[cfg(feature = "std")]
+pub mod labeled_ref;
+#[cfg(feature = "std")]
+pub mod labeled_fs;
+#[cfg(feature = "alloc")]
+pub mod labeled_string;
+#[cfg(feature = "alloc")]
+pub mod policy;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod store;
+pub mod static_label;
+#[cfg(feature = "std")]
+pub mod sync;
+pub mod tainted;
 pub mod twolevel;
 
+mod error;
+
+pub use error::{Error, Result};
+
 #[cfg(test)]
 mod qc_tests;
 
@@ -0,0 +1,359 @@
+//! Policy documents declaring principals, groups, named labels and clearances.
+//!
+//! A policy is a sequence of declarations, one per line. Blank lines and everything after a `#`
+//! are ignored.
+//!
+//! ```text
+//! principal alice
+//! principal bob
+//! group hr = alice, bob
+//! label pii = <alice /\ hr, True>
+//! clearance alice = pii
+//! clearance bob = <bob /\ hr, True>
+//! ```
+//!
+//! Every name used in a group, label or clearance must be declared, in any order. A group may
+//! contain principals and other groups; wherever a group appears in a label it stands for the
+//! disjunction of its members, so `hr` above reads as `alice \/ bob`. A clearance is either a
+//! label or the name of a declared label.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::context::Context;
+use crate::dclabel::{is_bare_principal, Conjunction, DCLabel, Disjunction, Principal, Privilege};
+
+/// An error in a policy document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyError {
+    line: usize,
+    message: String,
+}
+
+impl PolicyError {
+    fn new<M: Into<String>>(line: usize, message: M) -> Self {
+        PolicyError { line, message: message.into() }
+    }
+
+    /// The 1-based line number of the offending declaration.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PolicyError {}
+
+/// A validated policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    principals: BTreeSet<Principal>,
+    groups: BTreeMap<String, BTreeSet<Principal>>,
+    labels: BTreeMap<String, DCLabel>,
+    clearances: BTreeMap<Principal, DCLabel>,
+}
+
+impl Policy {
+    /// The declared principals.
+    pub fn principals(&self) -> impl Iterator<Item = &Principal> {
+        self.principals.iter()
+    }
+
+    /// The principals belonging to `group`, including through nested groups.
+    pub fn members(&self, group: &str) -> Option<&BTreeSet<Principal>> {
+        self.groups.get(group)
+    }
+
+    /// The declared labels, with groups expanded.
+    pub fn labels(&self) -> impl Iterator<Item = (&String, &DCLabel)> {
+        self.labels.iter()
+    }
+
+    /// The label declared as `name`, with groups expanded.
+    pub fn label(&self, name: &str) -> Option<&DCLabel> {
+        self.labels.get(name)
+    }
+
+    /// The clearance of `principal`, if one was declared.
+    pub fn clearance(&self, principal: &str) -> Option<&DCLabel> {
+        self.clearances.get(principal)
+    }
+
+    /// The privilege of acting as `principal`, if it is declared.
+    pub fn privilege(&self, principal: &str) -> Option<Privilege> {
+        if self.principals.contains(principal) {
+            Some(Privilege::new(principal))
+        } else {
+            None
+        }
+    }
+
+    /// A fresh context for `principal`: public, and bounded by its clearance.
+    pub fn context(&self, principal: &str) -> Option<Context<DCLabel>> {
+        let clearance = self.clearance(principal)?.clone();
+        Context::new(DCLabel::public(), clearance).ok()
+    }
+}
+
+enum Decl<'a> {
+    Principal(&'a str),
+    Group(&'a str, Vec<&'a str>),
+    Label(&'a str, &'a str),
+    Clearance(&'a str, &'a str),
+}
+
+fn parse_name(line: usize, name: &str) -> Result<&str, PolicyError> {
+    if is_bare_principal(name) {
+        Ok(name)
+    } else {
+        Err(PolicyError::new(line, format!("invalid name {:?}", name)))
+    }
+}
+
+fn parse_decl(line: usize, text: &str) -> Result<Decl<'_>, PolicyError> {
+    let (head, value) = match text.find('=') {
+        Some(i) => (&text[..i], Some(text[i + 1..].trim())),
+        None => (text, None),
+    };
+    let mut words = head.split_whitespace();
+    let keyword = words.next().unwrap_or_default();
+    let name = match (words.next(), words.next()) {
+        (Some(name), None) => parse_name(line, name)?,
+        _ => return Err(PolicyError::new(line, "expected a keyword followed by a single name")),
+    };
+    match (keyword, value) {
+        ("principal", None) => Ok(Decl::Principal(name)),
+        ("group", Some(members)) => {
+            let members = members.split(',')
+                .map(|m| parse_name(line, m.trim()))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Decl::Group(name, members))
+        }
+        ("label", Some(label)) => Ok(Decl::Label(name, label)),
+        ("clearance", Some(label)) => Ok(Decl::Clearance(name, label)),
+        ("principal", Some(_)) => Err(PolicyError::new(line, "a principal declaration takes no value")),
+        ("group", None) | ("label", None) | ("clearance", None) => {
+            Err(PolicyError::new(line, format!("expected `{} {} = ...`", keyword, name)))
+        }
+        _ => Err(PolicyError::new(line, format!("unknown declaration `{}`", keyword))),
+    }
+}
+
+/// Expand `group`, and any groups nested in it, into the principals it contains.
+fn expand_group(
+    group: &str,
+    decls: &BTreeMap<&str, (usize, Vec<&str>)>,
+    principals: &BTreeSet<Principal>,
+    done: &mut BTreeMap<String, BTreeSet<Principal>>,
+    visiting: &mut Vec<String>,
+) -> Result<BTreeSet<Principal>, PolicyError> {
+    if let Some(members) = done.get(group) {
+        return Ok(members.clone());
+    }
+    let (line, members) = &decls[group];
+    if visiting.iter().any(|g| g == group) {
+        return Err(PolicyError::new(*line, format!("group `{}` contains itself", group)));
+    }
+    visiting.push(group.to_string());
+    let mut expanded = BTreeSet::new();
+    for member in members {
+        if principals.contains(*member) {
+            expanded.insert(member.to_string());
+        } else if decls.contains_key(member) {
+            expanded.extend(expand_group(member, decls, principals, done, visiting)?);
+        } else {
+            let message = format!("undeclared principal or group `{}`", member);
+            return Err(PolicyError::new(*line, message));
+        }
+    }
+    visiting.pop();
+    done.insert(group.to_string(), expanded.clone());
+    Ok(expanded)
+}
+
+impl Policy {
+    /// Resolve groups in `label`, checking that every principal it mentions is declared.
+    fn expand_label(&self, line: usize, text: &str) -> Result<DCLabel, PolicyError> {
+        let label: DCLabel = text.parse()
+            .map_err(|e| PolicyError::new(line, format!("invalid label: {}", e)))?;
+        let expand = |conj: &Conjunction| -> Result<Conjunction, PolicyError> {
+            let mut out = Conjunction::mk_true();
+            for clause in conj.iter() {
+                let mut disj = Disjunction::mk_false();
+                for p in clause.iter() {
+                    if let Some(members) = self.groups.get(p) {
+                        for m in members {
+                            disj = disj | m.as_str();
+                        }
+                    } else if self.principals.contains(p) {
+                        disj = disj | p.as_str();
+                    } else {
+                        let message = format!("undeclared principal or group `{}`", p);
+                        return Err(PolicyError::new(line, message));
+                    }
+                }
+                out = out.add(disj);
+            }
+            Ok(out)
+        };
+        Ok(DCLabel::new(expand(label.secrecy())?, expand(label.integrity())?))
+    }
+}
+
+impl FromStr for Policy {
+    type Err = PolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut decls = Vec::new();
+        let mut names: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut clearances: BTreeMap<&str, usize> = BTreeMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let line_no = i + 1;
+            let text = line.split('#').next().unwrap_or_default().trim();
+            if text.is_empty() {
+                continue;
+            }
+            let decl = parse_decl(line_no, text)?;
+            let (name, seen) = match &decl {
+                Decl::Principal(n) | Decl::Group(n, _) | Decl::Label(n, _) => (*n, &mut names),
+                Decl::Clearance(n, _) => (*n, &mut clearances),
+            };
+            if let Some(prev) = seen.insert(name, line_no) {
+                let message = format!("`{}` is already declared on line {}", name, prev);
+                return Err(PolicyError::new(line_no, message));
+            }
+            decls.push((line_no, decl));
+        }
+
+        let mut policy = Policy {
+            principals: BTreeSet::new(),
+            groups: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            clearances: BTreeMap::new(),
+        };
+        let mut groups = BTreeMap::new();
+        for (line, decl) in decls.iter() {
+            match decl {
+                Decl::Principal(name) => {
+                    policy.principals.insert(name.to_string());
+                }
+                Decl::Group(name, members) => {
+                    groups.insert(*name, (*line, members.clone()));
+                }
+                _ => {}
+            }
+        }
+        for group in groups.keys() {
+            expand_group(group, &groups, &policy.principals, &mut policy.groups, &mut Vec::new())?;
+        }
+        for (line, decl) in decls.iter() {
+            if let Decl::Label(name, text) = decl {
+                let label = policy.expand_label(*line, text)?;
+                policy.labels.insert(name.to_string(), label);
+            }
+        }
+        for (line, decl) in decls.iter() {
+            if let Decl::Clearance(name, text) = decl {
+                if !policy.principals.contains(*name) {
+                    return Err(PolicyError::new(*line, format!("undeclared principal `{}`", name)));
+                }
+                let label = match policy.labels.get(*text) {
+                    Some(label) => label.clone(),
+                    None if is_bare_principal(text) => {
+                        return Err(PolicyError::new(*line, format!("undeclared label `{}`", text)));
+                    }
+                    None => policy.expand_label(*line, text)?,
+                };
+                policy.clearances.insert(name.to_string(), label);
+            }
+        }
+        Ok(policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = "
+        # People
+        principal alice
+        principal bob
+        principal carol
+        group hr = alice, bob
+        group staff = hr, carol   # nested
+
+        label pii = <alice /\\ hr, True>
+        label public = <True, True>
+        clearance alice = pii
+        clearance carol = <staff, True>
+    ";
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn load_policy() {
+        let policy: Policy = POLICY.parse().unwrap();
+        assert_eq!(policy.principals().count(), 3);
+        assert_eq!(policy.members("staff").unwrap().len(), 3);
+        assert_eq!(policy.label("pii"), Some(&label("<alice /\\ (alice \\/ bob), True>")));
+        assert_eq!(policy.label("pii"), Some(&label("<alice, True>")));
+        assert_eq!(policy.clearance("alice"), policy.label("pii"));
+        assert_eq!(policy.clearance("carol"), Some(&label("<alice \\/ bob \\/ carol, True>")));
+        assert_eq!(policy.clearance("bob"), None);
+    }
+
+    #[test]
+    fn privileges_and_contexts() {
+        let policy: Policy = POLICY.parse().unwrap();
+        let pii = policy.label("pii").unwrap();
+        let alice = policy.privilege("alice").unwrap();
+        assert!(pii.can_flow_to_with(&DCLabel::public(), &alice));
+        assert!(!pii.can_flow_to_with(&DCLabel::public(), &policy.privilege("bob").unwrap()));
+        assert_eq!(policy.privilege("hr"), None);
+
+        let mut ctx = policy.context("carol").unwrap();
+        assert!(ctx.taint(pii).is_err());
+        assert!(policy.context("alice").unwrap().taint(pii).is_ok());
+        assert!(policy.context("bob").is_none());
+    }
+
+    fn error(policy: &str) -> PolicyError {
+        policy.parse::<Policy>().unwrap_err()
+    }
+
+    #[test]
+    fn undeclared_references() {
+        assert_eq!(error("principal a\nlabel l = <a /\\ b, True>").line(), 2);
+        assert_eq!(error("principal a\ngroup g = a, b").line(), 2);
+        assert_eq!(error("principal a\nclearance b = <a, True>").line(), 2);
+        assert_eq!(error("principal a\nclearance a = secret").line(), 2);
+    }
+
+    #[test]
+    fn malformed_declarations() {
+        assert_eq!(error("principal a\nprincipal a").line(), 2);
+        assert_eq!(error("\n\nrole a").line(), 3);
+        assert_eq!(error("label l").line(), 1);
+        assert_eq!(error("principal a\nlabel l = <a").line(), 2);
+        assert_eq!(error("principal a b").line(), 1);
+    }
+
+    #[test]
+    fn group_cycles() {
+        let e = error("principal a\ngroup g = a, h\ngroup h = g");
+        assert!(e.to_string().contains("contains itself"), "{}", e);
+    }
+}
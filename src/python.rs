@@ -0,0 +1,384 @@
+//! Python bindings, built as the `label` extension module by the `label-python` crate in
+//! `python/`.
+//!
+//! The classes wrap [`DCLabel`], [`Conjunction`], [`Disjunction`] and [`TwoLevel`]. Their `&` and
+//! `|` operators mirror the Rust `BitAnd` and `BitOr` impls, and accept the same operand types.
+//! Invalid label text raises `ValueError`. Labels pickle as their text form.
+//!
+//! The tests run Python code in an embedded interpreter: `cargo test --features python`.
+
+// The code generated by `#[pymethods]` converts `PyErr` into itself.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::PyTypeInfo;
+use pyo3::types::PyType;
+
+use crate::Label;
+use crate::dclabel::{Conjunction, DCLabel, Disjunction};
+use crate::twolevel::TwoLevel;
+
+fn parse<T: core::str::FromStr>(text: &str) -> PyResult<T>
+where
+    T::Err: core::fmt::Display,
+{
+    text.parse().map_err(|e: T::Err| PyValueError::new_err(e.to_string()))
+}
+
+/// `(cls.parse, (text,))`, for `__reduce__`.
+fn reduce<'py, T: PyTypeInfo>(py: Python<'py>, text: String) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+    Ok((py.get_type_bound::<T>().getattr("parse")?, (text,)))
+}
+
+/// Anything with an `Into<Conjunction>` impl.
+#[derive(FromPyObject)]
+enum IntoConjunction {
+    Conjunction(PyConjunction),
+    Disjunction(PyDisjunction),
+    Bool(bool),
+    Principal(String),
+}
+
+impl From<IntoConjunction> for Conjunction {
+    fn from(c: IntoConjunction) -> Self {
+        match c {
+            IntoConjunction::Conjunction(c) => c.0,
+            IntoConjunction::Disjunction(d) => d.0.into(),
+            IntoConjunction::Bool(b) => b.into(),
+            IntoConjunction::Principal(p) => p.into(),
+        }
+    }
+}
+
+/// The right-hand side of `Conjunction`'s operators.
+#[derive(FromPyObject)]
+enum ConjunctionOperand {
+    Conjunction(PyConjunction),
+    Principal(String),
+}
+
+/// The right-hand side of `Disjunction.__or__`.
+#[derive(FromPyObject)]
+enum DisjunctionOperand {
+    Disjunction(PyDisjunction),
+    Principal(String),
+}
+
+#[pyclass(name = "Disjunction", module = "label", frozen, eq, hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PyDisjunction(Disjunction);
+
+#[pymethods]
+impl PyDisjunction {
+    /// The disjunction of `principals`; with none, the unsatisfiable clause `()`.
+    #[new]
+    #[pyo3(signature = (*principals))]
+    fn new(principals: Vec<String>) -> Self {
+        PyDisjunction(principals.into_iter().fold(Disjunction::mk_false(), Disjunction::add))
+    }
+
+    #[classmethod]
+    fn parse(_cls: &Bound<'_, PyType>, text: &str) -> PyResult<Self> {
+        parse(text).map(PyDisjunction)
+    }
+
+    fn principals(&self) -> Vec<String> {
+        self.0.iter().cloned().collect()
+    }
+
+    fn implies(&self, rhs: &Self) -> bool {
+        self.0.implies(&rhs.0)
+    }
+
+    fn __or__(&self, rhs: DisjunctionOperand) -> Self {
+        match rhs {
+            DisjunctionOperand::Disjunction(d) => PyDisjunction(&self.0 | &d.0),
+            DisjunctionOperand::Principal(p) => PyDisjunction(self.0.clone() | p),
+        }
+    }
+
+    fn __and__(&self, rhs: &Self) -> PyConjunction {
+        PyConjunction(self.0.clone() & rhs.0.clone())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Disjunction.parse({:?})", self.0.to_string())
+    }
+
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce::<Self>(py, self.0.to_string())
+    }
+}
+
+#[pyclass(name = "Conjunction", module = "label", frozen, eq, hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PyConjunction(Conjunction);
+
+#[pymethods]
+impl PyConjunction {
+    /// Converts a `Conjunction`, `Disjunction`, `bool` or principal name, like `Into<Conjunction>`.
+    #[new]
+    #[pyo3(signature = (value = IntoConjunction::Bool(true)))]
+    fn new(value: IntoConjunction) -> Self {
+        PyConjunction(value.into())
+    }
+
+    #[classmethod]
+    fn parse(_cls: &Bound<'_, PyType>, text: &str) -> PyResult<Self> {
+        parse(text).map(PyConjunction)
+    }
+
+    fn clauses(&self) -> Vec<PyDisjunction> {
+        self.0.iter().cloned().map(PyDisjunction).collect()
+    }
+
+    fn implies(&self, rhs: &Self) -> bool {
+        self.0.implies(&rhs.0)
+    }
+
+    fn __and__(&self, rhs: ConjunctionOperand) -> Self {
+        match rhs {
+            ConjunctionOperand::Conjunction(c) => PyConjunction(self.0.clone() & c.0),
+            ConjunctionOperand::Principal(p) => PyConjunction(self.0.clone() & p),
+        }
+    }
+
+    fn __or__(&self, rhs: ConjunctionOperand) -> Self {
+        match rhs {
+            ConjunctionOperand::Conjunction(c) => PyConjunction(self.0.clone() | c.0),
+            ConjunctionOperand::Principal(p) => PyConjunction(self.0.clone() | p),
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Conjunction.parse({:?})", self.0.to_string())
+    }
+
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce::<Self>(py, self.0.to_string())
+    }
+}
+
+#[pyclass(name = "DCLabel", module = "label", frozen, eq, hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PyDCLabel(DCLabel);
+
+#[pymethods]
+impl PyDCLabel {
+    #[new]
+    fn new(secrecy: IntoConjunction, integrity: IntoConjunction) -> Self {
+        PyDCLabel(DCLabel::new(secrecy, integrity))
+    }
+
+    #[classmethod]
+    fn parse(_cls: &Bound<'_, PyType>, text: &str) -> PyResult<Self> {
+        parse(text).map(PyDCLabel)
+    }
+
+    #[classmethod]
+    fn public(_cls: &Bound<'_, PyType>) -> Self {
+        PyDCLabel(DCLabel::public())
+    }
+
+    #[classmethod]
+    fn top(_cls: &Bound<'_, PyType>) -> Self {
+        PyDCLabel(DCLabel::top())
+    }
+
+    #[classmethod]
+    fn bottom(_cls: &Bound<'_, PyType>) -> Self {
+        PyDCLabel(DCLabel::bottom())
+    }
+
+    #[getter]
+    fn secrecy(&self) -> PyConjunction {
+        PyConjunction(self.0.secrecy().clone())
+    }
+
+    #[getter]
+    fn integrity(&self) -> PyConjunction {
+        PyConjunction(self.0.integrity().clone())
+    }
+
+    fn join(&self, rhs: &Self) -> Self {
+        PyDCLabel(self.0.join(&rhs.0))
+    }
+
+    fn meet(&self, rhs: &Self) -> Self {
+        PyDCLabel(self.0.meet(&rhs.0))
+    }
+
+    fn can_flow_to(&self, rhs: &Self) -> bool {
+        self.0.can_flow_to(&rhs.0)
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DCLabel.parse({:?})", self.0.to_string())
+    }
+
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce::<Self>(py, self.0.to_string())
+    }
+}
+
+#[pyclass(name = "TwoLevel", module = "label", frozen, eq, hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PyTwoLevel(TwoLevel);
+
+#[pymethods]
+impl PyTwoLevel {
+    #[classattr]
+    const LOW: PyTwoLevel = PyTwoLevel(TwoLevel::Low);
+    #[classattr]
+    const HIGH: PyTwoLevel = PyTwoLevel(TwoLevel::High);
+
+    /// `TwoLevel.parse("Low")` or `TwoLevel.parse("High")`.
+    #[classmethod]
+    fn parse(_cls: &Bound<'_, PyType>, text: &str) -> PyResult<Self> {
+        match text {
+            "Low" => Ok(PyTwoLevel(TwoLevel::Low)),
+            "High" => Ok(PyTwoLevel(TwoLevel::High)),
+            _ => Err(PyValueError::new_err(format!("invalid two-level label {:?}", text))),
+        }
+    }
+
+    fn join(&self, rhs: &Self) -> Self {
+        PyTwoLevel(self.0.join(&rhs.0))
+    }
+
+    fn meet(&self, rhs: &Self) -> Self {
+        PyTwoLevel(self.0.meet(&rhs.0))
+    }
+
+    fn can_flow_to(&self, rhs: &Self) -> bool {
+        self.0.can_flow_to(&rhs.0)
+    }
+
+    fn __str__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("TwoLevel.{}", self.__str__().to_uppercase())
+    }
+
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (String,))> {
+        reduce::<Self>(py, self.__str__())
+    }
+}
+
+/// The `label` Python module.
+#[pymodule]
+#[pyo3(name = "label")]
+pub fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDCLabel>()?;
+    m.add_class::<PyConjunction>()?;
+    m.add_class::<PyDisjunction>()?;
+    m.add_class::<PyTwoLevel>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    /// Run `code` with the module imported as `label`.
+    fn run(code: &str) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let m = PyModule::new_bound(py, "label").unwrap();
+            init(&m).unwrap();
+            py.import_bound("sys").unwrap().getattr("modules").unwrap().set_item("label", &m).unwrap();
+            let globals = PyDict::new_bound(py);
+            globals.set_item("label", &m).unwrap();
+            if let Err(e) = py.run_bound(code, Some(&globals), None) {
+                e.print(py);
+                panic!("python code failed");
+            }
+        });
+    }
+
+    #[test]
+    fn dclabel() {
+        run(r#"
+from label import DCLabel, Conjunction
+secret = DCLabel.parse(r"<alice /\ bob, True>")
+alice = DCLabel("alice", True)
+assert alice.can_flow_to(secret)
+assert not secret.can_flow_to(alice)
+assert alice.join(DCLabel("bob", True)) == secret
+assert alice.meet(secret) == alice
+assert secret.secrecy == Conjunction("alice") & "bob"
+assert str(secret) == r"<(alice) /\ (bob), True>"
+assert DCLabel.public().can_flow_to(DCLabel.top())
+assert len({secret, DCLabel.parse(r"<bob /\ alice, True>")}) == 1
+try:
+    DCLabel.parse("<alice")
+    assert False
+except ValueError as e:
+    assert "expected" in str(e)
+"#);
+    }
+
+    #[test]
+    fn operators() {
+        run(r#"
+from label import Conjunction, Disjunction
+d = Disjunction("alice") | "bob"
+assert d == Disjunction("alice", "bob") == Disjunction.parse(r"bob \/ alice")
+assert d | Disjunction("carol") == Disjunction("alice", "bob", "carol")
+c = Disjunction("alice") & Disjunction("bob")
+assert c == Conjunction.parse(r"alice /\ bob")
+assert (Conjunction("alice") | "bob") == Conjunction(d)
+assert c.implies(Conjunction("alice"))
+assert Conjunction() == Conjunction(True) == Conjunction.parse("True")
+try:
+    d & "carol"
+    assert False
+except TypeError:
+    pass
+"#);
+    }
+
+    #[test]
+    fn two_level() {
+        run(r#"
+from label import TwoLevel
+assert TwoLevel.LOW.can_flow_to(TwoLevel.HIGH)
+assert not TwoLevel.HIGH.can_flow_to(TwoLevel.LOW)
+assert TwoLevel.LOW.join(TwoLevel.HIGH) == TwoLevel.HIGH
+assert TwoLevel.LOW.meet(TwoLevel.HIGH) == TwoLevel.LOW
+assert repr(TwoLevel.parse("High")) == "TwoLevel.HIGH"
+"#);
+    }
+
+    #[test]
+    fn pickling() {
+        run(r#"
+import pickle
+from label import DCLabel, Conjunction, Disjunction, TwoLevel
+for value in [
+    DCLabel.parse(r'<alice /\ "bob smith", carol \/ dave>'),
+    DCLabel.top(),
+    Conjunction(False),
+    Disjunction(),
+    TwoLevel.HIGH,
+]:
+    assert pickle.loads(pickle.dumps(value)) == value, value
+"#);
+    }
+}
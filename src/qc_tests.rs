@@ -1,23 +1,53 @@
+/// A checker for properties over DCLabels that are too slow to run at the default generator size,
+/// where `|` on the generated conjunctions dominates the run time.
+#[cfg(feature = "alloc")]
+pub(crate) fn small() -> quickcheck::QuickCheck<quickcheck::StdThreadGen> {
+    quickcheck::QuickCheck::new().gen(quickcheck::StdThreadGen::new(10)).tests(50)
+}
+
 // DCLabel
+#[cfg(feature = "alloc")]
 mod dcl {
     use crate::Label;
     use crate::dclabel::*;
-    quickcheck! {
-        fn join(l1: DCLabel, l2: DCLabel) -> bool {
-            let ljoin = l1.join(&l2);
+    use crate::labeled::Labeled;
 
-            ljoin == l2.join(&l1) &&
-                l1.can_flow_to(&ljoin) &&
-                l2.can_flow_to(&ljoin)
-        }
+    fn join(l1: DCLabel, l2: DCLabel) -> bool {
+        let ljoin = l1.join(&l2);
 
-        fn meet(l1: DCLabel, l2: DCLabel) -> bool {
-            let lmeet = l1.meet(&l2);
+        ljoin == l2.join(&l1) &&
+            l1.can_flow_to(&ljoin) &&
+            l2.can_flow_to(&ljoin)
+    }
 
-            lmeet == l2.meet(&l1) &&
-                lmeet.can_flow_to(&l1) &&
-                lmeet.can_flow_to(&l2)
-        }
+    fn meet(l1: DCLabel, l2: DCLabel) -> bool {
+        let lmeet = l1.meet(&l2);
+
+        lmeet == l2.meet(&l1) &&
+            lmeet.can_flow_to(&l1) &&
+            lmeet.can_flow_to(&l2)
+    }
+
+    #[test]
+    fn join_is_upper_bound() {
+        super::small().quickcheck(join as fn(DCLabel, DCLabel) -> bool);
+    }
+
+    #[test]
+    fn meet_is_lower_bound() {
+        super::small().quickcheck(meet as fn(DCLabel, DCLabel) -> bool);
+    }
+
+    fn zip_with(l1: DCLabel, l2: DCLabel, x: u8, y: u8) -> bool {
+        let sum = Labeled::new(l1.clone(), x)
+            .zip_with(Labeled::new(l2.clone(), y), |x, y| x as u16 + y as u16);
+
+        sum.label() == &l1.join(&l2) && sum.value() == &(x as u16 + y as u16)
+    }
+
+    #[test]
+    fn zip_with_joins_labels() {
+        super::small().quickcheck(zip_with as fn(DCLabel, DCLabel, u8, u8) -> bool);
     }
 }
 
@@ -0,0 +1,214 @@
+//! Labels checked at compile time.
+//!
+//! A [`Secret<T, L>`] carries its label `L` as a type. Labels are zero-sized types ordered by the
+//! [`CanFlowTo`] and [`Join`] traits, so an illegal flow is a missing trait impl and fails to
+//! compile, at no runtime cost. [`Low`] and [`High`] mirror [`TwoLevel`](crate::twolevel::TwoLevel);
+//! [`static_chain!`](crate::static_chain) defines other totally ordered lattices.
+//!
+//! Reading a secret takes a [`StaticContext`], the static counterpart of a
+//! [`Context`](crate::context::Context), whose label the secret's label must flow to. A context
+//! starts at the bottom of its chain and is only raised by consuming it.
+//!
+//! ```
+//! use label::static_label::{High, Low, Secret, StaticContext};
+//!
+//! let salary: Secret<u32, High> = Secret::new(100);
+//! let bonus: Secret<u32, Low> = Secret::new(10);
+//! let total: Secret<u32, High> = salary.join_with(bonus, |s, b| s + b);
+//! let ctx = StaticContext::<Low>::new().raise::<High>();
+//! assert_eq!(*total.read(&ctx), 110);
+//! ```
+//!
+//! ```compile_fail
+//! use label::static_label::{High, Low, Secret};
+//!
+//! let salary: Secret<u32, High> = Secret::new(100);
+//! let leaked: Secret<u32, Low> = salary.relabel();
+//! ```
+
+use core::fmt;
+use core::marker::PhantomData;
+
+/// `Self` can flow to `Dst`: the static counterpart of [`Label::can_flow_to`](crate::Label::can_flow_to).
+pub trait CanFlowTo<Dst> {}
+
+/// The least upper bound of `Self` and `Rhs`: the static counterpart of
+/// [`Label::join`](crate::Label::join).
+pub trait Join<Rhs> {
+    type Output;
+}
+
+/// The lowest label of a chain, at which a [`StaticContext`] starts.
+pub trait Bottom {}
+
+/// Define a chain of static labels, lowest first, with their [`CanFlowTo`] and [`Join`] impls.
+///
+/// ```
+/// label::static_chain!(pub Public < Internal < Confidential);
+///
+/// use label::static_label::Secret;
+/// let memo: Secret<&str, Internal> = Secret::new("offsite agenda");
+/// let memo: Secret<&str, Confidential> = memo.relabel();
+/// # let _ = memo;
+/// ```
+#[macro_export]
+macro_rules! static_chain {
+    ($vis:vis $first:ident $(< $rest:ident)*) => {
+        $(
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+            $vis struct $rest;
+        )*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        $vis struct $first;
+        impl $crate::static_label::Bottom for $first {}
+        $crate::static_chain!(@impls $first $($rest)*);
+    };
+    (@impls $first:ident $($rest:ident)*) => {
+        impl $crate::static_label::CanFlowTo<$first> for $first {}
+        impl $crate::static_label::Join<$first> for $first {
+            type Output = $first;
+        }
+        $(
+            impl $crate::static_label::CanFlowTo<$rest> for $first {}
+            impl $crate::static_label::Join<$rest> for $first {
+                type Output = $rest;
+            }
+            impl $crate::static_label::Join<$first> for $rest {
+                type Output = $rest;
+            }
+        )*
+        $crate::static_chain!(@impls $($rest)*);
+    };
+    (@impls) => {};
+}
+
+static_chain!(pub Low < High);
+
+/// The static label `L` of a computation, which may read secrets whose label flows to `L`.
+///
+/// A context is neither `Clone` nor `Copy`, so once raised the lower context is gone.
+pub struct StaticContext<L> {
+    label: PhantomData<L>,
+}
+
+impl<L: Bottom> StaticContext<L> {
+    /// A context at the bottom of its chain.
+    pub fn new() -> Self {
+        StaticContext { label: PhantomData }
+    }
+}
+
+impl<L: Bottom> Default for StaticContext<L> {
+    fn default() -> Self {
+        StaticContext::new()
+    }
+}
+
+impl<L> StaticContext<L> {
+    /// Raise the context to `Dst`, which `L` must flow to.
+    pub fn raise<Dst>(self) -> StaticContext<Dst>
+    where
+        L: CanFlowTo<Dst>,
+    {
+        StaticContext { label: PhantomData }
+    }
+}
+
+impl<L: fmt::Debug + Default> fmt::Debug for StaticContext<L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StaticContext({:?})", L::default())
+    }
+}
+
+/// A value of type `T` protected by the static label `L`.
+#[derive(Clone, Copy)]
+pub struct Secret<T, L> {
+    value: T,
+    label: PhantomData<L>,
+}
+
+impl<T, L> Secret<T, L> {
+    pub fn new(value: T) -> Self {
+        Secret { value, label: PhantomData }
+    }
+
+    /// Move the value to a label that `L` can flow to.
+    pub fn relabel<Dst>(self) -> Secret<T, Dst>
+    where
+        L: CanFlowTo<Dst>,
+    {
+        Secret::new(self.value)
+    }
+
+    /// Read the value from a computation whose context is `ctx`, which `L` must flow to.
+    pub fn read<Ctx>(&self, _ctx: &StaticContext<Ctx>) -> &T
+    where
+        L: CanFlowTo<Ctx>,
+    {
+        &self.value
+    }
+
+    /// Compute on the value without revealing it. The result keeps the label `L`.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Secret<U, L> {
+        Secret::new(f(self.value))
+    }
+
+    /// Combine with another secret. The result is labeled with the join of both labels.
+    pub fn join_with<U, R, V, F>(self, rhs: Secret<U, R>, f: F) -> Secret<V, <L as Join<R>>::Output>
+    where
+        L: Join<R>,
+        F: FnOnce(T, U) -> V,
+    {
+        Secret::new(f(self.value, rhs.value))
+    }
+}
+
+impl<T, L: fmt::Debug + Default> fmt::Debug for Secret<T, L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secret({:?}, <hidden>)", L::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static_chain!(Unclassified < Restricted < Classified < TopSecret);
+
+    /// Compiles only if `A` flows to `B`.
+    fn flows<A: CanFlowTo<B>, B>() {}
+
+    fn join_of<A: Join<B>, B>(_: A, _: B) -> PhantomData<A::Output> {
+        PhantomData
+    }
+
+    #[test]
+    fn two_level() {
+        flows::<Low, Low>();
+        flows::<Low, High>();
+        flows::<High, High>();
+        let _: PhantomData<High> = join_of(Low, High);
+        let _: PhantomData<Low> = join_of(Low, Low);
+    }
+
+    #[test]
+    fn chain() {
+        flows::<Unclassified, TopSecret>();
+        flows::<Restricted, Classified>();
+        flows::<Classified, Classified>();
+        let _: PhantomData<Classified> = join_of(Classified, Restricted);
+        let _: PhantomData<TopSecret> = join_of(Unclassified, TopSecret);
+    }
+
+    #[test]
+    fn secrets() {
+        let a: Secret<i32, Restricted> = Secret::new(2);
+        let b: Secret<i32, Classified> = Secret::new(3);
+        let c = a.join_with(b, |x, y| x * y).map(|x| x + 1);
+        let ctx = StaticContext::<Unclassified>::new().raise::<TopSecret>();
+        assert_eq!(*c.read(&ctx), 7);
+        assert_eq!(format!("{:?}", ctx), "StaticContext(TopSecret)");
+        let c: Secret<i32, TopSecret> = c.relabel();
+        assert_eq!(format!("{:?}", c), "Secret(TopSecret, <hidden>)");
+    }
+}
@@ -0,0 +1,414 @@
+//! Key-value stores in which every entry carries its own label.
+//!
+//! [`MemoryStore`] keeps entries in memory, and [`LogStore`] persists them to an append-only log
+//! that can be compacted. Both implement [`LabeledStore`], so code can be written (and tested)
+//! against either.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::Label;
+use crate::context::Context;
+use crate::labeled::Labeled;
+
+/// Errors returned by [`LabeledStore`] operations, shared with [`labeled_fs`](crate::labeled_fs).
+/// A log record that cannot be decoded is [`Error::Corrupt`].
+pub use crate::labeled_fs::{Error, Result};
+
+/// A key-value store whose entries are individually labeled.
+pub trait LabeledStore<K, V, L> {
+    /// Look up `key`, tainting `ctx` with the entry's label if it is present.
+    ///
+    /// An entry whose label cannot flow to the clearance of `ctx` is treated as absent. Whether
+    /// an entry is absent depends on the entries of every label up to the clearance, so `None`
+    /// taints `ctx` with its clearance.
+    fn get(&self, ctx: &mut Context<L>, key: &K) -> Result<Option<V>>;
+
+    /// Insert or replace the entry for `key`.
+    ///
+    /// The current label of `ctx` must be able to flow to `label` and, when replacing an entry,
+    /// to the label of the existing entry. The latter check leaks that `key` is taken, by an entry
+    /// `ctx` may not be able to read, when it fails: keys should not themselves be secret.
+    fn put(&mut self, ctx: &Context<L>, key: K, label: L, value: V) -> Result<()>;
+
+    /// Remove the entry for `key`, which is a write to that entry.
+    ///
+    /// As with [`put`](LabeledStore::put), failing to remove an entry reveals that it exists.
+    fn remove(&mut self, ctx: &Context<L>, key: &K) -> Result<()>;
+
+    /// All entries whose label can flow to the clearance of `ctx`, in key order.
+    ///
+    /// Values are returned still labeled, so that listing the store does not taint `ctx` with
+    /// the label of every entry.
+    fn iter(&self, ctx: &Context<L>) -> Vec<(K, Labeled<L, V>)>;
+}
+
+/// An in-memory [`LabeledStore`].
+pub struct MemoryStore<K, V, L> {
+    entries: BTreeMap<K, Labeled<L, V>>,
+}
+
+impl<K: Ord, V, L> MemoryStore<K, V, L> {
+    pub fn new() -> Self {
+        MemoryStore { entries: BTreeMap::new() }
+    }
+}
+
+impl<K: Ord, V, L> Default for MemoryStore<K, V, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone, L: Label + Clone> MemoryStore<K, V, L> {
+    fn guard_replace(&self, ctx: &Context<L>, key: &K) -> Result<()> {
+        if let Some(old) = self.entries.get(key) {
+            ctx.guard_write(old.label())?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone, V: Clone, L: Label + Clone> LabeledStore<K, V, L> for MemoryStore<K, V, L> {
+    fn get(&self, ctx: &mut Context<L>, key: &K) -> Result<Option<V>> {
+        match self.entries.get(key) {
+            Some(entry) if entry.label().can_flow_to(ctx.clearance()) => Ok(Some(ctx.unlabel(entry)?)),
+            _ => {
+                let clearance = ctx.clearance().clone();
+                ctx.taint(&clearance)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn put(&mut self, ctx: &Context<L>, key: K, label: L, value: V) -> Result<()> {
+        self.guard_replace(ctx, &key)?;
+        let entry = ctx.label_value(label, value)?;
+        self.entries.insert(key, entry);
+        Ok(())
+    }
+
+    fn remove(&mut self, ctx: &Context<L>, key: &K) -> Result<()> {
+        self.guard_replace(ctx, key)?;
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    fn iter(&self, ctx: &Context<L>) -> Vec<(K, Labeled<L, V>)> {
+        self.entries.iter()
+            .filter(|(_, entry)| entry.label().can_flow_to(ctx.clearance()))
+            .map(|(key, entry)| (key.clone(), entry.clone()))
+            .collect()
+    }
+}
+
+/// A [`LabeledStore`] persisted to an append-only log.
+///
+/// Every `put` and `remove` appends a record to the log, and the log is replayed when the store
+/// is opened. Keys, values and labels are stored using their `Display` and `FromStr`
+/// implementations. Superseded records accumulate until [`LogStore::compact`] rewrites the log
+/// with only the live entries.
+pub struct LogStore<K, V, L> {
+    path: PathBuf,
+    log: File,
+    memory: MemoryStore<K, V, L>,
+    stale: usize,
+}
+
+impl<K, V, L> LogStore<K, V, L>
+where
+    K: Ord + Clone + fmt::Display + FromStr,
+    V: Clone + fmt::Display + FromStr,
+    L: Label + Clone + fmt::Display + FromStr,
+{
+    /// Open the store logged at `path`, creating an empty log if it does not exist.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let mut memory = MemoryStore::new();
+        let mut stale = 0;
+        if path.exists() {
+            let contents = fs::read_to_string(&path)?;
+            let (records, valid) = parse_log(&contents)?;
+            if valid < contents.len() {
+                OpenOptions::new().write(true).open(&path)?.set_len(valid as u64)?;
+            }
+            for record in records {
+                let key = decode(record.key)?;
+                let replaced = match record.entry {
+                    Some((label, value)) => {
+                        let entry = Labeled::new(decode(label)?, decode(value)?);
+                        memory.entries.insert(key, entry).is_some()
+                    }
+                    None => {
+                        stale += 1;
+                        memory.entries.remove(&key).is_some()
+                    }
+                };
+                if replaced {
+                    stale += 1;
+                }
+            }
+        }
+        let log = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(LogStore { path, log, memory, stale })
+    }
+
+    /// The number of records in the log that no longer describe a live entry.
+    pub fn stale_records(&self) -> usize {
+        self.stale
+    }
+
+    /// Rewrite the log so that it contains exactly one record per live entry.
+    ///
+    /// The new log is written to a temporary file and renamed over the old one, so a crash
+    /// during compaction leaves the previous log intact.
+    pub fn compact(&mut self) -> Result<()> {
+        let tmp = self.path.with_extension("compact");
+        {
+            let mut file = File::create(&tmp)?;
+            for (key, entry) in self.memory.entries.iter() {
+                write_put(&mut file, key, entry.label(), entry.value())?;
+            }
+            file.sync_all()?;
+        }
+        fs::rename(&tmp, &self.path)?;
+        self.log = OpenOptions::new().append(true).open(&self.path)?;
+        self.stale = 0;
+        Ok(())
+    }
+}
+
+impl<K, V, L> LabeledStore<K, V, L> for LogStore<K, V, L>
+where
+    K: Ord + Clone + fmt::Display + FromStr,
+    V: Clone + fmt::Display + FromStr,
+    L: Label + Clone + fmt::Display + FromStr,
+{
+    fn get(&self, ctx: &mut Context<L>, key: &K) -> Result<Option<V>> {
+        self.memory.get(ctx, key)
+    }
+
+    fn put(&mut self, ctx: &Context<L>, key: K, label: L, value: V) -> Result<()> {
+        self.memory.guard_replace(ctx, &key)?;
+        ctx.guard_write(&label)?;
+        write_put(&mut self.log, &key, &label, &value)?;
+        if self.memory.entries.insert(key, Labeled::new(label, value)).is_some() {
+            self.stale += 1;
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, ctx: &Context<L>, key: &K) -> Result<()> {
+        self.memory.guard_replace(ctx, key)?;
+        if self.memory.entries.contains_key(key) {
+            write_field(&mut self.log, "D", &[&key.to_string()])?;
+            self.memory.entries.remove(key);
+            self.stale += 2;
+        }
+        Ok(())
+    }
+
+    fn iter(&self, ctx: &Context<L>) -> Vec<(K, Labeled<L, V>)> {
+        self.memory.iter(ctx)
+    }
+}
+
+// Each record is a header line holding a tag and the byte length of every field, followed by
+// the fields themselves and a newline:
+//
+//     P 5 3 2
+//     <a,b>key42
+//     D 3
+//     key
+
+fn write_put<K: fmt::Display, V: fmt::Display, L: fmt::Display>(
+    w: &mut dyn Write, key: &K, label: &L, value: &V,
+) -> io::Result<()> {
+    write_field(w, "P", &[&label.to_string(), &key.to_string(), &value.to_string()])
+}
+
+fn write_field(w: &mut dyn Write, tag: &str, fields: &[&str]) -> io::Result<()> {
+    let mut record = String::from(tag);
+    for field in fields {
+        record.push_str(&format!(" {}", field.len()));
+    }
+    record.push('\n');
+    for field in fields {
+        record.push_str(field);
+    }
+    record.push('\n');
+    w.write_all(record.as_bytes())
+}
+
+struct Record<'a> {
+    key: &'a str,
+    entry: Option<(&'a str, &'a str)>,
+}
+
+fn decode<T: FromStr>(s: &str) -> Result<T> {
+    s.parse().map_err(|_| Error::Corrupt(format!("cannot decode {:?}", s)))
+}
+
+/// Split a log into records, also returning the length of the log they span. A torn record at
+/// the very end of the log, left by a crash during an append, is not included.
+fn parse_log(log: &str) -> Result<(Vec<Record<'_>>, usize)> {
+    let mut records = Vec::new();
+    let mut contents = log;
+    while !contents.is_empty() {
+        let header_end = match contents.find('\n') {
+            Some(i) => i,
+            None => break,
+        };
+        let mut header = contents[..header_end].split(' ');
+        let tag = header.next().unwrap_or_default();
+        let lens = header.map(|n| n.parse::<usize>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::Corrupt(format!("bad header {:?}", &contents[..header_end])))?;
+        let body_len: usize = lens.iter().sum();
+        let body = &contents[header_end + 1..];
+        if body.len() < body_len + 1 {
+            break;
+        }
+        if !body.is_char_boundary(body_len) || body.as_bytes()[body_len] != b'\n' {
+            return Err(Error::Corrupt("record length mismatch".to_string()));
+        }
+        let mut fields = Vec::new();
+        let mut offset = 0;
+        for len in lens.iter() {
+            fields.push(body.get(offset..offset + len)
+                .ok_or_else(|| Error::Corrupt("field is not valid UTF-8".to_string()))?);
+            offset += len;
+        }
+        let record = match (tag, fields.as_slice()) {
+            ("P", [label, key, value]) => Record { key, entry: Some((label, value)) },
+            ("D", [key]) => Record { key, entry: None },
+            _ => return Err(Error::Corrupt(format!("bad record tag {:?}", tag))),
+        };
+        records.push(record);
+        contents = &body[body_len + 1..];
+    }
+    Ok((records, log.len() - contents.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dclabel::DCLabel;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn temp_log() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let path = std::env::temp_dir().join(format!("label-store-{}-{}.log", std::process::id(), n));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn label(s: &str) -> DCLabel {
+        s.parse().unwrap()
+    }
+
+    fn exercise<S: LabeledStore<String, String, DCLabel>>(store: &mut S) {
+        let public = Context::new(DCLabel::public(), label("<alice, True>")).unwrap();
+        store.put(&public, "motd".to_string(), DCLabel::public(), "hi".to_string()).unwrap();
+        store.put(&public, "ssn".to_string(), label("<alice, True>"), "123".to_string()).unwrap();
+        let writer = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        store.put(&writer, "salary".to_string(), label("<bob, True>"), "9".to_string()).unwrap();
+
+        // Listing only shows what the clearance allows, and does not taint.
+        let visible: Vec<String> = store.iter(&public).into_iter().map(|(k, _)| k).collect();
+        assert_eq!(visible, vec!["motd".to_string(), "ssn".to_string()]);
+        assert_eq!(public.label(), &DCLabel::public());
+
+        let mut reader = public.clone();
+        assert_eq!(store.get(&mut reader, &"ssn".to_string()).unwrap(), Some("123".to_string()));
+        assert_eq!(reader.label(), &label("<alice, True>"));
+
+        // An entry above the clearance looks exactly like a missing one.
+        let mut prober = public.clone();
+        assert_eq!(store.get(&mut prober, &"salary".to_string()).unwrap(), None);
+        assert_eq!(prober.label(), &label("<alice, True>"));
+        let mut prober = public.clone();
+        assert_eq!(store.get(&mut prober, &"nothing".to_string()).unwrap(), None);
+        assert_eq!(prober.label(), &label("<alice, True>"));
+
+        // A tainted reader can no longer write public entries, or overwrite them.
+        match store.put(&reader, "motd".to_string(), label("<alice, True>"), "leak".to_string()) {
+            Err(Error::Flow(crate::Error::FlowViolation)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        match store.remove(&reader, &"motd".to_string()) {
+            Err(Error::Flow(crate::Error::FlowViolation)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        store.put(&reader, "ssn".to_string(), label("<alice, True>"), "456".to_string()).unwrap();
+        store.remove(&writer, &"salary".to_string()).unwrap();
+    }
+
+    #[test]
+    fn memory_store() {
+        let mut store = MemoryStore::new();
+        exercise(&mut store);
+        let all = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        assert_eq!(store.iter(&all).len(), 2);
+    }
+
+    #[test]
+    fn log_store_replays() {
+        let path = temp_log();
+        let mut store = LogStore::open(&path).unwrap();
+        exercise(&mut store);
+        assert_eq!(store.stale_records(), 3);
+        drop(store);
+
+        let store = LogStore::<String, String, DCLabel>::open(&path).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let keys: Vec<String> = store.iter(&ctx).into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["motd".to_string(), "ssn".to_string()]);
+        assert_eq!(store.get(&mut ctx, &"ssn".to_string()).unwrap(), Some("456".to_string()));
+        assert_eq!(ctx.label(), &label("<alice, True>"));
+        assert_eq!(store.stale_records(), 3);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn log_store_compacts() {
+        let path = temp_log();
+        let mut store = LogStore::open(&path).unwrap();
+        exercise(&mut store);
+        let before = fs::metadata(&path).unwrap().len();
+        store.compact().unwrap();
+        assert_eq!(store.stale_records(), 0);
+        assert!(fs::metadata(&path).unwrap().len() < before);
+
+        let ctx = Context::new(DCLabel::public(), DCLabel::public()).unwrap();
+        store.put(&ctx, "multi\nline".to_string(), DCLabel::public(), "a b\n1 2".to_string()).unwrap();
+        drop(store);
+
+        let store = LogStore::<String, String, DCLabel>::open(&path).unwrap();
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        assert_eq!(store.iter(&ctx).len(), 3);
+        assert_eq!(store.get(&mut ctx, &"multi\nline".to_string()).unwrap(), Some("a b\n1 2".to_string()));
+        assert_eq!(store.stale_records(), 0);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn log_store_ignores_torn_record() {
+        let path = temp_log();
+        fs::write(&path, "P 12 1 1\n<True, True>ab\nP 12 1").unwrap();
+        let mut store = LogStore::<String, String, DCLabel>::open(&path).unwrap();
+        let ctx = Context::new(DCLabel::public(), DCLabel::public()).unwrap();
+        assert_eq!(store.iter(&ctx).len(), 1);
+        store.put(&ctx, "c".to_string(), DCLabel::public(), "d".to_string()).unwrap();
+        drop(store);
+
+        let store = LogStore::<String, String, DCLabel>::open(&path).unwrap();
+        assert_eq!(store.iter(&ctx).len(), 2);
+        fs::remove_file(path).unwrap();
+    }
+}
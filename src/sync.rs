@@ -0,0 +1,273 @@
+//! Blocking synchronization objects protected by a fixed label.
+//!
+//! Acquiring a [`LabeledMutex`] or taking from a [`LabeledMVar`] is both an observation, since
+//! whether it blocks reveals what other computations are doing, and a write, since it changes
+//! what they will observe. These operations therefore require [`Context::guard_write`] on the
+//! object's label and then taint the current label with it. Without the write check a
+//! computation that has seen high data could signal a low one through the lock state alone.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, TryLockError};
+
+use crate::Label;
+use crate::context::Context;
+use crate::error::Result;
+
+/// A mutual exclusion lock protected by a fixed label.
+pub struct LabeledMutex<L, T> {
+    label: L,
+    inner: Mutex<T>,
+}
+
+impl<L: Label + Clone, T> LabeledMutex<L, T> {
+    /// Create a mutex labeled `label`, holding `value`. `label` must satisfy
+    /// [`Context::guard_write`].
+    pub fn new(ctx: &Context<L>, label: L, value: T) -> Result<Self> {
+        ctx.guard_write(&label)?;
+        Ok(LabeledMutex { label, inner: Mutex::new(value) })
+    }
+
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    /// Block until the lock is acquired.
+    ///
+    /// The write check and taint happen before blocking. The guard keeps `ctx` borrowed, so the
+    /// current label cannot rise while the lock is held, and the write check made here covers
+    /// every modification made through the guard.
+    pub fn lock<'a>(&'a self, ctx: &'a mut Context<L>) -> Result<LabeledMutexGuard<'a, L, T>> {
+        ctx.guard_write(&self.label)?;
+        ctx.taint(&self.label)?;
+        let inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(LabeledMutexGuard { ctx, inner })
+    }
+
+    /// Acquire the lock if it is free, with the same checks as [`LabeledMutex::lock`].
+    pub fn try_lock<'a>(&'a self, ctx: &'a mut Context<L>) -> Result<Option<LabeledMutexGuard<'a, L, T>>> {
+        ctx.guard_write(&self.label)?;
+        ctx.taint(&self.label)?;
+        let inner = match self.inner.try_lock() {
+            Ok(inner) => inner,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return Ok(None),
+        };
+        Ok(Some(LabeledMutexGuard { ctx, inner }))
+    }
+}
+
+impl<L: std::fmt::Debug, T> std::fmt::Debug for LabeledMutex<L, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "LabeledMutex({:?}, <hidden>)", self.label)
+    }
+}
+
+/// Access to the contents of a locked [`LabeledMutex`]. The lock is released on drop.
+pub struct LabeledMutexGuard<'a, L, T> {
+    ctx: &'a mut Context<L>,
+    inner: MutexGuard<'a, T>,
+}
+
+impl<L, T> LabeledMutexGuard<'_, L, T> {
+    /// The context that acquired the lock.
+    pub fn context(&self) -> &Context<L> {
+        self.ctx
+    }
+}
+
+impl<L, T> Deref for LabeledMutexGuard<'_, L, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<L, T> DerefMut for LabeledMutexGuard<'_, L, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+struct MVarState<T> {
+    value: Mutex<Option<T>>,
+    changed: Condvar,
+}
+
+/// A labeled slot that is either full or empty, after LIO's `LMVar`.
+///
+/// [`take`](LabeledMVar::take) blocks until the slot is full and empties it;
+/// [`put`](LabeledMVar::put) blocks until it is empty and fills it. Both are reads and writes of
+/// the slot. Clones share the same slot.
+pub struct LabeledMVar<L, T> {
+    label: L,
+    state: Arc<MVarState<T>>,
+}
+
+impl<L: Label + Clone, T> LabeledMVar<L, T> {
+    /// Create a full slot labeled `label`. `label` must satisfy [`Context::guard_write`].
+    pub fn new(ctx: &Context<L>, label: L, value: T) -> Result<Self> {
+        Self::with_value(ctx, label, Some(value))
+    }
+
+    /// Create an empty slot labeled `label`. `label` must satisfy [`Context::guard_write`].
+    pub fn empty(ctx: &Context<L>, label: L) -> Result<Self> {
+        Self::with_value(ctx, label, None)
+    }
+
+    fn with_value(ctx: &Context<L>, label: L, value: Option<T>) -> Result<Self> {
+        ctx.guard_write(&label)?;
+        let state = MVarState { value: Mutex::new(value), changed: Condvar::new() };
+        Ok(LabeledMVar { label, state: Arc::new(state) })
+    }
+
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    fn access(&self, ctx: &mut Context<L>) -> Result<MutexGuard<'_, Option<T>>> {
+        ctx.guard_write(&self.label)?;
+        ctx.taint(&self.label)?;
+        Ok(self.state.value.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn wait<'a>(&self, slot: MutexGuard<'a, Option<T>>, full: bool) -> MutexGuard<'a, Option<T>> {
+        self.state.changed
+            .wait_while(slot, |v| v.is_some() != full)
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Block until the slot is full, then empty it and return its contents.
+    pub fn take(&self, ctx: &mut Context<L>) -> Result<T> {
+        let slot = self.access(ctx)?;
+        let value = self.wait(slot, true).take();
+        self.state.changed.notify_all();
+        Ok(value.expect("slot is full"))
+    }
+
+    /// Take the contents if the slot is full, without blocking.
+    pub fn try_take(&self, ctx: &mut Context<L>) -> Result<Option<T>> {
+        let value = self.access(ctx)?.take();
+        if value.is_some() {
+            self.state.changed.notify_all();
+        }
+        Ok(value)
+    }
+
+    /// Block until the slot is empty, then fill it with `value`.
+    pub fn put(&self, ctx: &mut Context<L>, value: T) -> Result<()> {
+        let slot = self.access(ctx)?;
+        *self.wait(slot, false) = Some(value);
+        self.state.changed.notify_all();
+        Ok(())
+    }
+
+    /// Block until the slot is full, and return a copy of its contents, leaving it full.
+    pub fn read(&self, ctx: &mut Context<L>) -> Result<T>
+    where
+        T: Clone,
+    {
+        let slot = self.access(ctx)?;
+        Ok(self.wait(slot, true).clone().expect("slot is full"))
+    }
+}
+
+impl<L: Clone, T> Clone for LabeledMVar<L, T> {
+    fn clone(&self) -> Self {
+        LabeledMVar { label: self.label.clone(), state: self.state.clone() }
+    }
+}
+
+impl<L: std::fmt::Debug, T> std::fmt::Debug for LabeledMVar<L, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "LabeledMVar({:?}, <hidden>)", self.label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use crate::twolevel::TwoLevel;
+    use std::thread;
+
+    fn ctx(label: TwoLevel) -> Context<TwoLevel> {
+        Context::new(label, TwoLevel::High).unwrap()
+    }
+
+    #[test]
+    fn lock_taints() {
+        let m = LabeledMutex::new(&ctx(TwoLevel::Low), TwoLevel::High, vec![1]).unwrap();
+        let mut c = ctx(TwoLevel::Low);
+        m.lock(&mut c).unwrap().push(2);
+        assert_eq!(c.label(), &TwoLevel::High);
+        assert_eq!(*m.lock(&mut c).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn high_thread_cannot_signal_through_mutex() {
+        let m = Arc::new(LabeledMutex::new(&ctx(TwoLevel::Low), TwoLevel::Low, 0).unwrap());
+        let high = {
+            let m = m.clone();
+            thread::spawn(move || {
+                let mut c = ctx(TwoLevel::High);
+                // Holding the lock would make the low thread's `try_lock` fail.
+                (m.lock(&mut c).err(), m.try_lock(&mut c).err())
+            })
+        };
+        assert_eq!(high.join().unwrap(), (Some(Error::FlowViolation), Some(Error::FlowViolation)));
+
+        let mut low = ctx(TwoLevel::Low);
+        let mut guard = m.try_lock(&mut low).unwrap().expect("lock is free");
+        *guard += 1;
+        assert_eq!(guard.context().label(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn mutex_clearance() {
+        let m = LabeledMutex::new(&ctx(TwoLevel::Low), TwoLevel::High, 0).unwrap();
+        let mut c = Context::new(TwoLevel::Low, TwoLevel::Low).unwrap();
+        assert_eq!(m.lock(&mut c).err(), Some(Error::ClearanceViolation));
+        assert_eq!(c.label(), &TwoLevel::Low);
+    }
+
+    #[test]
+    fn mvar_hands_off_between_threads() {
+        let mvar = LabeledMVar::empty(&ctx(TwoLevel::Low), TwoLevel::High).unwrap();
+        let producer = {
+            let mvar = mvar.clone();
+            thread::spawn(move || {
+                let mut c = ctx(TwoLevel::Low);
+                for i in 0..3 {
+                    mvar.put(&mut c, i).unwrap();
+                }
+            })
+        };
+        let mut c = ctx(TwoLevel::Low);
+        let received: Vec<i32> = (0..3).map(|_| mvar.take(&mut c).unwrap()).collect();
+        producer.join().unwrap();
+        assert_eq!(received, vec![0, 1, 2]);
+        assert_eq!(c.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn high_thread_cannot_signal_through_mvar() {
+        let mvar = LabeledMVar::new(&ctx(TwoLevel::Low), TwoLevel::Low, 1).unwrap();
+        let high = {
+            let mvar = mvar.clone();
+            thread::spawn(move || {
+                let mut c = ctx(TwoLevel::High);
+                // Emptying or reading the slot would be visible to the low thread.
+                (mvar.try_take(&mut c), mvar.read(&mut c), mvar.put(&mut c, 2))
+            })
+        };
+        let flow = Error::FlowViolation;
+        assert_eq!(high.join().unwrap(), (Err(flow.clone()), Err(flow.clone()), Err(flow)));
+
+        let mut low = ctx(TwoLevel::Low);
+        assert_eq!(mvar.read(&mut low), Ok(1));
+        assert_eq!(mvar.try_take(&mut low), Ok(Some(1)));
+        assert_eq!(mvar.try_take(&mut low), Ok(None));
+        assert_eq!(low.label(), &TwoLevel::Low);
+    }
+}
@@ -0,0 +1,293 @@
+//! Taint-tracked values with operator overloading.
+//!
+//! A [`Tainted`] value is a labeled value that can be computed with directly: applying an
+//! operator to tainted operands produces a tainted result labeled with the join of their labels.
+//! Operands that are not tainted, such as constants, count as public. Like a [`Labeled`] value,
+//! the result is only read through a [`Context`](crate::context::Context), after converting it
+//! into a `Labeled`.
+
+use core::fmt;
+use core::ops::{
+    Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div, DivAssign,
+    Mul, MulAssign, Neg, Not, Rem, RemAssign, Shl, ShlAssign, Shr, ShrAssign, Sub, SubAssign,
+};
+
+use crate::Label;
+use crate::labeled::Labeled;
+
+/// A value whose label follows it through arithmetic.
+#[derive(Clone, Copy)]
+pub struct Tainted<L, T> {
+    label: L,
+    value: T,
+}
+
+impl<L, T> Tainted<L, T> {
+    pub fn new(label: L, value: T) -> Self {
+        Tainted { label, value }
+    }
+
+    pub fn label(&self) -> &L {
+        &self.label
+    }
+
+    /// Apply `f` to the value, keeping the label.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Tainted<L, U> {
+        Tainted { label: self.label, value: f(self.value) }
+    }
+}
+
+impl<L: Label, T> Tainted<L, T> {
+    fn compare<U, F: FnOnce(&T, &U) -> bool>(&self, rhs: &Tainted<L, U>, f: F) -> Tainted<L, bool> {
+        Tainted { label: self.label.join(&rhs.label), value: f(&self.value, &rhs.value) }
+    }
+
+    /// Whether the values are equal, labeled with the join of both labels.
+    pub fn is_eq<U>(&self, rhs: &Tainted<L, U>) -> Tainted<L, bool>
+    where
+        T: PartialEq<U>,
+    {
+        self.compare(rhs, |a, b| a == b)
+    }
+
+    /// Whether the values differ, labeled with the join of both labels.
+    pub fn is_ne<U>(&self, rhs: &Tainted<L, U>) -> Tainted<L, bool>
+    where
+        T: PartialEq<U>,
+    {
+        self.compare(rhs, |a, b| a != b)
+    }
+
+    /// Whether `self` is less than `rhs`, labeled with the join of both labels.
+    pub fn is_lt<U>(&self, rhs: &Tainted<L, U>) -> Tainted<L, bool>
+    where
+        T: PartialOrd<U>,
+    {
+        self.compare(rhs, |a, b| a < b)
+    }
+
+    /// Whether `self` is less than or equal to `rhs`, labeled with the join of both labels.
+    pub fn is_le<U>(&self, rhs: &Tainted<L, U>) -> Tainted<L, bool>
+    where
+        T: PartialOrd<U>,
+    {
+        self.compare(rhs, |a, b| a <= b)
+    }
+
+    /// Whether `self` is greater than `rhs`, labeled with the join of both labels.
+    pub fn is_gt<U>(&self, rhs: &Tainted<L, U>) -> Tainted<L, bool>
+    where
+        T: PartialOrd<U>,
+    {
+        self.compare(rhs, |a, b| a > b)
+    }
+
+    /// Whether `self` is greater than or equal to `rhs`, labeled with the join of both labels.
+    pub fn is_ge<U>(&self, rhs: &Tainted<L, U>) -> Tainted<L, bool>
+    where
+        T: PartialOrd<U>,
+    {
+        self.compare(rhs, |a, b| a >= b)
+    }
+}
+
+impl<L, T> From<Labeled<L, T>> for Tainted<L, T> {
+    fn from(labeled: Labeled<L, T>) -> Self {
+        let (label, value) = labeled.into_parts();
+        Tainted { label, value }
+    }
+}
+
+impl<L, T> From<Tainted<L, T>> for Labeled<L, T> {
+    fn from(tainted: Tainted<L, T>) -> Self {
+        Labeled::new(tainted.label, tainted.value)
+    }
+}
+
+impl<L: fmt::Debug, T> fmt::Debug for Tainted<L, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tainted({:?}, <hidden>)", self.label)
+    }
+}
+
+/// Binary operators between tainted values, and their assigning forms.
+macro_rules! tainted_binop {
+    ($($op:ident::$method:ident, $assign:ident::$assign_method:ident;)*) => {$(
+        impl<L: Label, T: $op<U>, U> $op<Tainted<L, U>> for Tainted<L, T> {
+            type Output = Tainted<L, T::Output>;
+
+            fn $method(self, rhs: Tainted<L, U>) -> Self::Output {
+                Tainted { label: self.label.join(&rhs.label), value: self.value.$method(rhs.value) }
+            }
+        }
+
+        impl<L: Label, T: $assign<U>, U> $assign<Tainted<L, U>> for Tainted<L, T> {
+            fn $assign_method(&mut self, rhs: Tainted<L, U>) {
+                self.label = self.label.join(&rhs.label);
+                self.value.$assign_method(rhs.value);
+            }
+        }
+    )*};
+}
+
+tainted_binop! {
+    Add::add, AddAssign::add_assign;
+    Sub::sub, SubAssign::sub_assign;
+    Mul::mul, MulAssign::mul_assign;
+    Div::div, DivAssign::div_assign;
+    Rem::rem, RemAssign::rem_assign;
+    BitAnd::bitand, BitAndAssign::bitand_assign;
+    BitOr::bitor, BitOrAssign::bitor_assign;
+    BitXor::bitxor, BitXorAssign::bitxor_assign;
+    Shl::shl, ShlAssign::shl_assign;
+    Shr::shr, ShrAssign::shr_assign;
+}
+
+impl<L, T: Neg> Neg for Tainted<L, T> {
+    type Output = Tainted<L, T::Output>;
+
+    fn neg(self) -> Self::Output {
+        self.map(Neg::neg)
+    }
+}
+
+impl<L, T: Not> Not for Tainted<L, T> {
+    type Output = Tainted<L, T::Output>;
+
+    fn not(self) -> Self::Output {
+        self.map(Not::not)
+    }
+}
+
+/// Binary operators between tainted values and public primitives, on either side.
+macro_rules! public_binop {
+    ($op:ident::$method:ident, $assign:ident::$assign_method:ident; $($t:ty)*) => {$(
+        impl<L> $op<$t> for Tainted<L, $t> {
+            type Output = Tainted<L, $t>;
+
+            fn $method(self, rhs: $t) -> Self::Output {
+                self.map(|value| value.$method(rhs))
+            }
+        }
+
+        impl<L> $op<Tainted<L, $t>> for $t {
+            type Output = Tainted<L, $t>;
+
+            fn $method(self, rhs: Tainted<L, $t>) -> Self::Output {
+                rhs.map(|value| self.$method(value))
+            }
+        }
+
+        impl<L> $assign<$t> for Tainted<L, $t> {
+            fn $assign_method(&mut self, rhs: $t) {
+                self.value.$assign_method(rhs);
+            }
+        }
+    )*};
+}
+
+macro_rules! public_arith {
+    ($($t:ty)*) => {
+        public_binop!(Add::add, AddAssign::add_assign; $($t)*);
+        public_binop!(Sub::sub, SubAssign::sub_assign; $($t)*);
+        public_binop!(Mul::mul, MulAssign::mul_assign; $($t)*);
+        public_binop!(Div::div, DivAssign::div_assign; $($t)*);
+        public_binop!(Rem::rem, RemAssign::rem_assign; $($t)*);
+    };
+}
+
+macro_rules! public_bits {
+    ($($t:ty)*) => {
+        public_binop!(BitAnd::bitand, BitAndAssign::bitand_assign; $($t)*);
+        public_binop!(BitOr::bitor, BitOrAssign::bitor_assign; $($t)*);
+        public_binop!(BitXor::bitxor, BitXorAssign::bitxor_assign; $($t)*);
+        public_binop!(Shl::shl, ShlAssign::shl_assign; $($t)*);
+        public_binop!(Shr::shr, ShrAssign::shr_assign; $($t)*);
+    };
+}
+
+public_arith!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize f32 f64);
+public_bits!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+#[cfg(feature = "alloc")]
+impl<'a, L> Add<&'a str> for Tainted<L, alloc::string::String> {
+    type Output = Tainted<L, alloc::string::String>;
+
+    fn add(self, rhs: &'a str) -> Self::Output {
+        self.map(|value| value + rhs)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, L> AddAssign<&'a str> for Tainted<L, alloc::string::String> {
+    fn add_assign(&mut self, rhs: &'a str) {
+        self.value += rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    #[cfg(feature = "alloc")]
+    use crate::dclabel::DCLabel;
+    use crate::twolevel::TwoLevel;
+
+    fn read<T: Clone>(ctx: &mut Context<TwoLevel>, tainted: Tainted<TwoLevel, T>) -> T {
+        ctx.unlabel(&tainted.into()).unwrap()
+    }
+
+    #[test]
+    fn arithmetic_joins_labels() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let price = Tainted::new(TwoLevel::Low, 20i64);
+        let quantity = Tainted::new(TwoLevel::High, 3i64);
+
+        let discounted = price * 2 - 5;
+        assert_eq!(discounted.label(), &TwoLevel::Low);
+        let total = discounted * quantity;
+        assert_eq!(total.label(), &TwoLevel::High);
+        assert_eq!(read(&mut ctx, -total), -105);
+        assert_eq!(ctx.label(), &TwoLevel::High);
+    }
+
+    #[test]
+    fn float_and_bits() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let mut mean = Tainted::new(TwoLevel::Low, 1.5f64) + Tainted::new(TwoLevel::Low, 2.5);
+        mean /= 2.0;
+        assert_eq!(read(&mut ctx, 1.0 + mean), 3.0);
+
+        let mut flags = Tainted::new(TwoLevel::Low, 0b1010u8);
+        flags |= Tainted::new(TwoLevel::High, 0b0001);
+        assert_eq!(flags.label(), &TwoLevel::High);
+        assert_eq!(read(&mut ctx, !(flags << 4)), 0b0100_1111);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn comparison_joins_labels() {
+        let alice: DCLabel = "<alice, True>".parse().unwrap();
+        let bob: DCLabel = "<bob, True>".parse().unwrap();
+        let a = Tainted::new(alice.clone(), 10u32);
+        let b = Tainted::new(bob.clone(), 12u32);
+
+        let richer = b.is_gt(&a);
+        assert_eq!(richer.label(), &alice.join(&bob));
+        let mut ctx = Context::new(DCLabel::public(), DCLabel::top()).unwrap();
+        let richer = ctx.branch_on(&richer.into(), |_| "bob", |_| "alice").unwrap();
+        assert_eq!(ctx.unlabel(&richer), Ok("bob"));
+        assert_eq!(a.is_eq(&b).label(), &alice.join(&bob));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn string_concatenation() {
+        let mut ctx = Context::new(TwoLevel::Low, TwoLevel::High).unwrap();
+        let name = Tainted::new(TwoLevel::High, "eve");
+        let mut greeting = Tainted::new(TwoLevel::Low, String::from("hello, ")) + name;
+        greeting += "!";
+        assert_eq!(greeting.label(), &TwoLevel::High);
+        assert_eq!(read(&mut ctx, greeting), "hello, eve!");
+    }
+}
@@ -1,6 +1,6 @@
 use crate::Label;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TwoLevel {
     Low,
     High
@@ -23,6 +23,7 @@ impl Label for TwoLevel {
         }
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     fn can_flow_to(&self, rhs: &Self) -> bool {
         match (self, rhs) {
             (TwoLevel::High, TwoLevel::High) => true,
@@ -0,0 +1,7 @@
+//! Programs with illegal static flows must not compile.
+
+#[test]
+fn static_label_flows() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile_fail/*.rs");
+}
@@ -0,0 +1,9 @@
+use label::static_label::{Low, Secret};
+
+label::static_chain!(Public < Internal);
+
+fn main() {
+    // Labels from different chains are incomparable.
+    let note: Secret<&str, Low> = Secret::new("lunch");
+    let _note: Secret<&str, Internal> = note.relabel::<Internal>();
+}
@@ -0,0 +1,8 @@
+use label::static_label::Secret;
+
+label::static_chain!(Public < Internal < Confidential);
+
+fn main() {
+    let memo: Secret<&str, Confidential> = Secret::new("layoffs");
+    let _memo: Secret<&str, Internal> = memo.relabel::<Internal>();
+}
@@ -0,0 +1,7 @@
+use label::static_label::{High, Secret, StaticContext};
+
+fn main() {
+    let salary: Secret<u32, High> = Secret::new(100);
+    let ctx = StaticContext::<High>::new();
+    println!("{}", salary.read(&ctx));
+}
@@ -0,0 +1,8 @@
+use label::labeled_format;
+use label::labeled_string::LabeledString;
+use label::twolevel::TwoLevel;
+
+fn main() {
+    let one = LabeledString::labeled(TwoLevel::Low, "one");
+    let _ = labeled_format!(TwoLevel::Low, "{} and {}", one);
+}
@@ -0,0 +1,7 @@
+use label::static_label::{High, Low, Secret};
+
+fn main() {
+    let salary: Secret<u32, High> = Secret::new(100);
+    let bonus: Secret<u32, Low> = Secret::new(10);
+    let _total: Secret<u32, Low> = bonus.join_with(salary, |b, s| b + s);
+}
@@ -0,0 +1,7 @@
+use label::static_label::{High, Low, Secret, StaticContext};
+
+fn main() {
+    let salary: Secret<u32, High> = Secret::new(100);
+    let ctx = StaticContext::<Low>::new();
+    println!("{}", salary.read(&ctx));
+}
@@ -0,0 +1,6 @@
+use label::static_label::{High, Low, Secret};
+
+fn main() {
+    let salary: Secret<u32, High> = Secret::new(100);
+    let _leaked: Secret<u32, Low> = salary.relabel::<Low>();
+}